@@ -1,3 +1,4 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::{
     msg,
@@ -17,11 +18,64 @@ pub enum VortexEvent {
         start_time: i64,
         duration: i64,
     },
+    VestedTokensClaimed {
+        beneficiary: Pubkey,
+        amount: u64,
+    },
+    VestingRevoked {
+        beneficiary: Pubkey,
+        unvested_amount: u64,
+    },
     StakeCreated {
         staker: Pubkey,
         amount: u64,
         duration: i64,
     },
+    Unstaked {
+        staker: Pubkey,
+        amount: u64,
+    },
+    RewardsClaimed {
+        staker: Pubkey,
+        amount: u64,
+    },
+    PauseStatusChanged {
+        admin: Pubkey,
+        paused: bool,
+    },
+    TokensBurned {
+        owner: Pubkey,
+        amount: u64,
+        circulating_supply: u64,
+    },
+    TokensMinted {
+        destination: Pubkey,
+        amount: u64,
+        total_supply: u64,
+    },
+    MintAuthorityChanged {
+        previous_authority: Pubkey,
+        new_authority: Option<Pubkey>,
+    },
+    AdminChanged {
+        previous_admin: Pubkey,
+        new_admin: Pubkey,
+    },
+    DistributorCreated {
+        distributor: Pubkey,
+        airdrop_id: u64,
+        total_amount: u64,
+        num_leaves: u64,
+    },
+    AirdropClaimed {
+        claimant: Pubkey,
+        index: u64,
+        amount: u64,
+    },
+    RewardEpochsAppended {
+        count: u16,
+        end_timestamp: i64,
+    },
     
     // Marketplace Events
     ArtworkListed {
@@ -40,6 +94,84 @@ pub enum VortexEvent {
         seller: Pubkey,
         nft_mint: Pubkey,
     },
+    ListingUpdated {
+        seller: Pubkey,
+        nft_mint: Pubkey,
+        price: u64,
+        expires_at: i64,
+    },
+    ExpiredListingClosed {
+        seller: Pubkey,
+        nft_mint: Pubkey,
+    },
+    MarketplaceConfigUpdated {
+        admin: Pubkey,
+        fee_bps: u16,
+        max_royalty_percentage: u8,
+        paused: bool,
+    },
+    RoyaltiesRegistered {
+        nft_mint: Pubkey,
+        royalty_percentage: u8,
+        creators: Vec<Pubkey>,
+    },
+    AuctionCreated {
+        auction: Pubkey,
+        seller: Pubkey,
+        nft_mint: Pubkey,
+        reserve: u64,
+        end: i64,
+    },
+    BidPlaced {
+        auction: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+        end: i64,
+    },
+    AuctionSettled {
+        auction: Pubkey,
+        winner: Option<Pubkey>,
+        amount: u64,
+    },
+    AuctionCancelled {
+        auction: Pubkey,
+    },
+    BidRefundWithdrawn {
+        auction: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+    },
+    OfferMade {
+        offer: Pubkey,
+        buyer: Pubkey,
+        nft_mint: Pubkey,
+        amount: u64,
+        expiry: i64,
+    },
+    OfferAccepted {
+        offer: Pubkey,
+        seller: Pubkey,
+        buyer: Pubkey,
+        nft_mint: Pubkey,
+        amount: u64,
+    },
+    OfferCancelled {
+        offer: Pubkey,
+    },
+    CollectionCreated {
+        collection: Pubkey,
+        authority: Pubkey,
+    },
+    CollectionMemberVerified {
+        collection: Pubkey,
+        nft_mint: Pubkey,
+    },
+    FloorSwept {
+        collection: Pubkey,
+        buyer: Pubkey,
+        count: u8,
+        total: u64,
+    },
     
     // Governance Events
     ProposalCreated {
@@ -59,34 +191,134 @@ pub enum VortexEvent {
         yes_votes: u64,
         no_votes: u64,
     },
+    GovernanceConfigUpdated {
+        admin: Pubkey,
+        quorum_bps: u16,
+        approval_threshold_bps: u16,
+        proposal_threshold: u64,
+    },
+    ProposalQueued {
+        proposal_id: Pubkey,
+        eta: i64,
+    },
+    ProposalCancelled {
+        proposal_id: Pubkey,
+    },
+    ProposalVetoed {
+        proposal_id: Pubkey,
+        security_council: Pubkey,
+    },
 }
 
 impl VortexEvent {
     pub fn log(&self) {
         let serialized = self.try_to_vec().unwrap();
-        msg!("EVENT:{}", base64::encode(&serialized));
+        msg!("EVENT:{}", BASE64.encode(&serialized));
         
         // Also log human-readable format
         match self {
             VortexEvent::TokenInitialized { mint, total_supply } => {
                 msg!("Token Initialized: Mint={}, Supply={}", mint, total_supply);
             }
-            VortexEvent::VestingCreated { beneficiary, amount, start_time, duration } => {
+            VortexEvent::VestingCreated { beneficiary, amount, .. } => {
                 msg!("Vesting Created: Beneficiary={}, Amount={}", beneficiary, amount);
             }
-            VortexEvent::StakeCreated { staker, amount, duration } => {
+            VortexEvent::VestedTokensClaimed { beneficiary, amount } => {
+                msg!("Vested Tokens Claimed: Beneficiary={}, Amount={}", beneficiary, amount);
+            }
+            VortexEvent::VestingRevoked { beneficiary, unvested_amount } => {
+                msg!("Vesting Revoked: Beneficiary={}, Unvested={}", beneficiary, unvested_amount);
+            }
+            VortexEvent::StakeCreated { staker, amount, .. } => {
                 msg!("Stake Created: Staker={}, Amount={}", staker, amount);
             }
-            VortexEvent::ArtworkListed { seller, nft_mint, price, royalty_percentage } => {
+            VortexEvent::Unstaked { staker, amount } => {
+                msg!("Unstaked: Staker={}, Amount={}", staker, amount);
+            }
+            VortexEvent::RewardsClaimed { staker, amount } => {
+                msg!("Rewards Claimed: Staker={}, Amount={}", staker, amount);
+            }
+            VortexEvent::PauseStatusChanged { admin, paused } => {
+                msg!("Pause Status Changed: Admin={}, Paused={}", admin, paused);
+            }
+            VortexEvent::TokensBurned { owner, amount, circulating_supply } => {
+                msg!("Tokens Burned: Owner={}, Amount={}, Circulating={}", owner, amount, circulating_supply);
+            }
+            VortexEvent::TokensMinted { destination, amount, total_supply } => {
+                msg!("Tokens Minted: Destination={}, Amount={}, Supply={}", destination, amount, total_supply);
+            }
+            VortexEvent::MintAuthorityChanged { previous_authority, new_authority } => {
+                msg!("Mint Authority Changed: From={}, To={:?}", previous_authority, new_authority);
+            }
+            VortexEvent::AdminChanged { previous_admin, new_admin } => {
+                msg!("Admin Changed: From={}, To={}", previous_admin, new_admin);
+            }
+            VortexEvent::DistributorCreated { airdrop_id, total_amount, num_leaves, .. } => {
+                msg!("Distributor Created: Airdrop={}, Amount={}, Leaves={}", airdrop_id, total_amount, num_leaves);
+            }
+            VortexEvent::AirdropClaimed { claimant, index, amount } => {
+                msg!("Airdrop Claimed: Claimant={}, Index={}, Amount={}", claimant, index, amount);
+            }
+            VortexEvent::RewardEpochsAppended { count, end_timestamp } => {
+                msg!("Reward Epochs Appended: Count={}, Ends={}", count, end_timestamp);
+            }
+            VortexEvent::ArtworkListed { seller, nft_mint, price, .. } => {
                 msg!("Artwork Listed: Seller={}, NFT={}, Price={}", seller, nft_mint, price);
             }
-            VortexEvent::ArtworkPurchased { buyer, seller, nft_mint, price } => {
+            VortexEvent::ArtworkPurchased { buyer, nft_mint, price, .. } => {
                 msg!("Artwork Purchased: Buyer={}, NFT={}, Price={}", buyer, nft_mint, price);
             }
             VortexEvent::ListingCancelled { seller, nft_mint } => {
                 msg!("Listing Cancelled: Seller={}, NFT={}", seller, nft_mint);
             }
-            VortexEvent::ProposalCreated { creator, proposal_id, title, voting_period } => {
+            VortexEvent::ListingUpdated { seller, nft_mint, price, expires_at } => {
+                msg!("Listing Updated: Seller={}, NFT={}, Price={}, ExpiresAt={}", seller, nft_mint, price, expires_at);
+            }
+            VortexEvent::ExpiredListingClosed { seller, nft_mint } => {
+                msg!("Expired Listing Closed: Seller={}, NFT={}", seller, nft_mint);
+            }
+            VortexEvent::MarketplaceConfigUpdated { admin, fee_bps, max_royalty_percentage, paused } => {
+                msg!("Marketplace Config Updated: Admin={}, Fee={}bps, MaxRoyalty={}%, Paused={}",
+                    admin, fee_bps, max_royalty_percentage, paused);
+            }
+            VortexEvent::RoyaltiesRegistered { nft_mint, royalty_percentage, creators } => {
+                msg!("Royalties Registered: NFT={}, Royalty={}%, Creators={}",
+                    nft_mint, royalty_percentage, creators.len());
+            }
+            VortexEvent::AuctionCreated { auction, nft_mint, reserve, end, .. } => {
+                msg!("Auction Created: Auction={}, NFT={}, Reserve={}, End={}", auction, nft_mint, reserve, end);
+            }
+            VortexEvent::BidPlaced { auction, bidder, amount, end } => {
+                msg!("Bid Placed: Auction={}, Bidder={}, Amount={}, End={}", auction, bidder, amount, end);
+            }
+            VortexEvent::AuctionSettled { auction, winner, amount } => {
+                msg!("Auction Settled: Auction={}, Winner={:?}, Amount={}", auction, winner, amount);
+            }
+            VortexEvent::AuctionCancelled { auction } => {
+                msg!("Auction Cancelled: Auction={}", auction);
+            }
+            VortexEvent::BidRefundWithdrawn { auction, bidder, amount } => {
+                msg!("Bid Refund Withdrawn: Auction={}, Bidder={}, Amount={}", auction, bidder, amount);
+            }
+            VortexEvent::OfferMade { buyer, nft_mint, amount, expiry, .. } => {
+                msg!("Offer Made: Buyer={}, NFT={}, Amount={}, Expiry={}", buyer, nft_mint, amount, expiry);
+            }
+            VortexEvent::OfferAccepted { seller, buyer, nft_mint, amount, .. } => {
+                msg!("Offer Accepted: Seller={}, Buyer={}, NFT={}, Amount={}", seller, buyer, nft_mint, amount);
+            }
+            VortexEvent::OfferCancelled { offer } => {
+                msg!("Offer Cancelled: Offer={}", offer);
+            }
+            VortexEvent::CollectionCreated { collection, authority } => {
+                msg!("Collection Created: Collection={}, Authority={}", collection, authority);
+            }
+            VortexEvent::CollectionMemberVerified { collection, nft_mint } => {
+                msg!("Collection Member Verified: Collection={}, NFT={}", collection, nft_mint);
+            }
+            VortexEvent::FloorSwept { collection, buyer, count, total } => {
+                msg!("Floor Swept: Collection={}, Buyer={}, Count={}, Total={}", collection, buyer, count, total);
+            }
+            VortexEvent::ProposalCreated { proposal_id, title, .. } => {
                 msg!("Proposal Created: ID={}, Title={}", proposal_id, title);
             }
             VortexEvent::VoteCast { voter, proposal_id, amount, vote } => {
@@ -97,6 +329,19 @@ impl VortexEvent {
                 msg!("Proposal Executed: ID={}, Yes={}, No={}", 
                     proposal_id, yes_votes, no_votes);
             }
+            VortexEvent::GovernanceConfigUpdated { admin, quorum_bps, approval_threshold_bps, proposal_threshold } => {
+                msg!("Governance Config Updated: Admin={}, Quorum={}bps, Approval={}bps, ProposalThreshold={}",
+                    admin, quorum_bps, approval_threshold_bps, proposal_threshold);
+            }
+            VortexEvent::ProposalQueued { proposal_id, eta } => {
+                msg!("Proposal Queued: ID={}, ETA={}", proposal_id, eta);
+            }
+            VortexEvent::ProposalCancelled { proposal_id } => {
+                msg!("Proposal Cancelled: ID={}", proposal_id);
+            }
+            VortexEvent::ProposalVetoed { proposal_id, security_council } => {
+                msg!("Proposal Vetoed: ID={}, SecurityCouncil={}", proposal_id, security_council);
+            }
         }
     }
 }
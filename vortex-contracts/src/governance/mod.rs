@@ -1,47 +1,217 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
     entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
     msg,
+    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    events::{emit, VortexEvent},
+    token::{self, get_stake, get_total_staked, get_voting_power, TolaState, STATE_SEED},
+    utils::create_pda_account,
+    VortexError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Seed of the `GovernanceConfig` PDA
+pub const GOVERNANCE_CONFIG_SEED: &[u8] = b"governance_config";
+/// Seed prefix of `VoteRecord` PDAs, followed by the proposal and voter
+pub const VOTE_RECORD_SEED: &[u8] = b"vote_record";
+/// Seed prefix of `Proposal` PDAs, followed by the proposal id as little-endian bytes
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+/// Seed of the PDA that signs the instructions of executed proposals
+pub const GOVERNANCE_AUTHORITY_SEED: &[u8] = b"governance_authority";
+/// Most instructions a single proposal may carry
+pub const MAX_PROPOSAL_INSTRUCTIONS: usize = 8;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum GovernanceInstruction {
-    /// Create a new proposal
+    /// Create a new proposal under the current governance parameters
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The proposal creator's account
-    /// 1. `[writable]` The proposal account
+    /// 0. `[signer, writable]` The proposal creator's account
+    /// 1. `[writable]` The proposal account (PDA `["proposal", proposal_count]`)
+    /// 2. `[writable]` The governance config account
+    /// 3. `[]` The TOLA stake pool account, for the total staked
+    /// 4. `[]` The creator's TOLA stake account
+    /// 5. `[]` The system program
     CreateProposal {
         title: String,
         description: String,
         voting_period: i64,
+        /// Run in order on execution, signed by the governance authority,
+        /// the only signer they may name
+        instructions: Vec<ProposalInstruction>,
     },
 
-    /// Cast a vote on a proposal
+    /// Cast a vote on a proposal, weighted by the veTOLA power of the
+    /// voter's stake, which must stay locked until voting ends
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The voter's account
+    /// 0. `[signer, writable]` The voter's account
     /// 1. `[writable]` The proposal account
-    /// 2. `[writable]` The voter's token account
+    /// 2. `[]` The voter's TOLA stake account
+    /// 3. `[writable]` The vote record account (PDA `["vote_record", proposal, voter]`)
+    /// 4. `[]` The governance config account
+    /// 5. `[]` The system program
     CastVote {
         vote: bool,
-        amount: u64,
     },
 
-    /// Execute a proposal
+    /// Execute a queued proposal once its timelock has passed and before its
+    /// grace period runs out, invoking its instructions with the governance
+    /// authority as signer. Anyone may execute; no signature is taken, so
+    /// nothing of the caller's can be spent by the proposal.
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The executor's account
-    /// 1. `[writable]` The proposal account
+    /// 0. `[writable]` The proposal account
+    /// 1. `[]` The governance authority (PDA `["governance_authority"]`)
+    /// 2. Every account and program the proposal's instructions reference, from here on
     ExecuteProposal {},
+
+    /// Bind governance to the TOLA token program; only its admin may do so
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The TOLA admin, who becomes governance admin
+    /// 1. `[writable]` The governance config account (PDA `["governance_config"]`)
+    /// 2. `[]` The TOLA state account, owned by the TOLA program (`token::ID`)
+    /// 3. `[]` The system program
+    InitializeGovernance {
+        params: GovernanceParams,
+        /// May veto proposals; the default pubkey disables vetoes
+        security_council: Pubkey,
+    },
+
+    /// Replace the governance parameters, admin and security council;
+    /// existing proposals keep their parameters
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current governance admin
+    /// 1. `[writable]` The governance config account
+    UpdateGovernanceConfig {
+        admin: Pubkey,
+        security_council: Pubkey,
+        params: GovernanceParams,
+    },
+
+    /// Queue a proposal that reached quorum and its approval threshold, making
+    /// it executable once the timelock delay after voting ends has passed
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Anyone
+    /// 1. `[writable]` The proposal account
+    QueueProposal {},
+
+    /// Withdraw a proposal before its voting ends
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The proposal creator
+    /// 1. `[writable]` The proposal account
+    CancelProposal {},
+
+    /// Block a proposal from ever executing, at any point before it does
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The security council named in the governance config
+    /// 1. `[writable]` The proposal account
+    /// 2. `[]` The governance config account
+    VetoProposal {},
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposalAccountMeta {
+    pub const LEN: usize = 32 + 1 + 1;
+}
+
+/// An instruction stored on a proposal until it is executed
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl ProposalInstruction {
+    /// Serialized size in bytes
+    pub fn size(&self) -> usize {
+        32 + 4 + self.accounts.len() * ProposalAccountMeta::LEN + 4 + self.data.len()
+    }
+}
+
+impl From<&Instruction> for ProposalInstruction {
+    fn from(instruction: &Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| ProposalAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+impl From<&ProposalInstruction> for Instruction {
+    fn from(instruction: &ProposalInstruction) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+/// Lifecycle of a proposal. Only some states are recorded; the rest follow
+/// from the clock, see `Proposal::state_at`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalState {
+    /// Created, voting not yet open
+    Draft,
+    /// Voting open
+    Active,
+    /// Voting ended without quorum or approval
+    Defeated,
+    /// Voting ended with quorum and approval; may be queued
+    Succeeded,
+    /// Waiting out the timelock, then executable until the grace period ends
+    Queued,
+    Executed,
+    /// Withdrawn by its creator
+    Cancelled,
+    /// Blocked by the security council
+    Vetoed,
+    /// Queued but not executed within the grace period
+    Expired,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct Proposal {
+    pub id: u64,
     pub creator: Pubkey,
     pub title: String,
     pub description: String,
@@ -49,11 +219,167 @@ pub struct Proposal {
     pub end_time: i64,
     pub yes_votes: u64,
     pub no_votes: u64,
-    pub executed: bool,
+    /// Last recorded state
+    pub state: ProposalState,
+    /// When a queued proposal becomes executable; 0 until it is queued
+    pub eta: i64,
+    /// Governance parameters in force when the proposal was created
+    pub params: GovernanceParams,
+    /// Votes needed for quorum, fixed from the TOLA staked at creation
+    pub quorum_votes: u64,
+    pub bump: u8,
+    pub instructions: Vec<ProposalInstruction>,
+}
+
+impl Proposal {
+    pub fn len(title: &str, description: &str, instructions: &[ProposalInstruction]) -> usize {
+        8 + 32 + 4 + title.len() + 4 + description.len() + 8 + 8 + 8 + 8 + 1 + 8 + GovernanceParams::LEN + 8 + 1
+            + 4 + instructions.iter().map(ProposalInstruction::size).sum::<usize>()
+    }
+
+    pub fn signer_seeds<'a>(&self, id: &'a [u8; 8], bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [PROPOSAL_SEED, id, bump]
+    }
+
+    /// The state in force at `now`, resolving those reached by time alone
+    pub fn state_at(&self, now: i64) -> ProposalState {
+        match self.state {
+            ProposalState::Draft if now < self.start_time => ProposalState::Draft,
+            ProposalState::Draft | ProposalState::Active if now <= self.end_time => ProposalState::Active,
+            ProposalState::Draft | ProposalState::Active => {
+                if self.check_passed().is_ok() {
+                    ProposalState::Succeeded
+                } else {
+                    ProposalState::Defeated
+                }
+            }
+            ProposalState::Queued if now > self.eta.saturating_add(self.params.grace_period) => {
+                ProposalState::Expired
+            }
+            state => state,
+        }
+    }
+
+    /// Check that the proposal may move to `next` at `now`, returning its
+    /// current state. Voting is the move from `Active` to `Active`.
+    pub fn check_transition(&self, now: i64, next: ProposalState) -> Result<ProposalState, ProgramError> {
+        use ProposalState::*;
+
+        let current = self.state_at(now);
+        let allowed = match next {
+            Active => current == Active,
+            Queued => current == Succeeded,
+            Executed => current == Queued && now >= self.eta,
+            Cancelled => matches!(current, Draft | Active),
+            Vetoed => matches!(current, Draft | Active | Succeeded | Queued),
+            Draft | Defeated | Succeeded | Expired => false,
+        };
+        if allowed {
+            return Ok(current);
+        }
+        let error = match (current, next) {
+            (Draft, _) => VortexError::VotingNotStarted,
+            (Active, _) => VortexError::VotingInProgress,
+            (Defeated, Queued) => {
+                self.check_passed()?;
+                VortexError::InvalidState
+            }
+            (Defeated | Succeeded, Executed) => VortexError::ProposalNotQueued,
+            (Queued, Queued) => VortexError::ProposalAlreadyQueued,
+            (Queued, Executed) => VortexError::TimelockActive,
+            (Defeated | Succeeded | Queued, _) => VortexError::VotingEnded,
+            (Executed, _) => VortexError::ProposalAlreadyExecuted,
+            (Cancelled, _) => VortexError::ProposalCancelled,
+            (Vetoed, _) => VortexError::ProposalVetoed,
+            (Expired, _) => VortexError::ProposalExpired,
+        };
+        Err(error.into())
+    }
+
+    /// Whether enough voted and enough of them voted yes
+    pub fn check_passed(&self) -> ProgramResult {
+        let total = self.yes_votes as u128 + self.no_votes as u128;
+        if total < self.quorum_votes as u128 || total == 0 {
+            return Err(VortexError::QuorumNotReached.into());
+        }
+        if self.yes_votes as u128 * 10_000 < total * self.params.approval_threshold_bps as u128 {
+            return Err(VortexError::ApprovalThresholdNotMet.into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct GovernanceParams {
+    /// Share of circulating TOLA that must vote for a result to count
+    pub quorum_bps: u16,
+    /// Share of the votes cast that must be yes
+    pub approval_threshold_bps: u16,
+    pub min_voting_period: i64,
+    pub max_voting_period: i64,
+    /// Voting weight a creator needs to open a proposal
+    pub proposal_threshold: u64,
+    /// Seconds between the end of voting and when a queued proposal may execute
+    pub timelock_delay: i64,
+    /// Seconds after its eta during which a queued proposal may still execute
+    pub grace_period: i64,
+    /// Seconds between creating a proposal and voting opening on it
+    pub voting_delay: i64,
+}
+
+impl GovernanceParams {
+    pub const LEN: usize = 2 + 2 + 8 + 8 + 8 + 8 + 8 + 8;
+
+    pub fn validate(&self) -> ProgramResult {
+        if self.quorum_bps > 10_000
+            || self.approval_threshold_bps == 0
+            || self.approval_threshold_bps > 10_000
+            || self.min_voting_period <= 0
+            || self.min_voting_period > self.max_voting_period
+            || self.timelock_delay < 0
+            || self.grace_period <= 0
+            || self.voting_delay < 0
+        {
+            return Err(VortexError::InvalidInstruction.into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct GovernanceConfig {
+    pub admin: Pubkey,
+    /// May veto proposals; the default pubkey when no one may
+    pub security_council: Pubkey,
+    /// The TOLA token program stake accounts are read from
+    pub tola_program: Pubkey,
+    pub params: GovernanceParams,
+    /// Proposals created so far, which also numbers the next one
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl GovernanceConfig {
+    pub const LEN: usize = 32 + 32 + 32 + GovernanceParams::LEN + 8 + 1;
+}
+
+/// Marks that `voter` has voted on `proposal`, and how
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub vote: bool,
+    pub weight: u64,
+    pub bump: u8,
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 1;
 }
 
 // Entry point
-entrypoint!(process_instruction);
+#[cfg(all(feature = "governance-program", not(feature = "no-entrypoint")))]
+solana_program::entrypoint!(process_instruction);
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -63,18 +389,38 @@ pub fn process_instruction(
     let instruction = GovernanceInstruction::try_from_slice(instruction_data)?;
 
     match instruction {
-        GovernanceInstruction::CreateProposal { title, description, voting_period } => {
+        GovernanceInstruction::CreateProposal { title, description, voting_period, instructions } => {
             msg!("Instruction: Create Proposal");
-            process_create_proposal(program_id, accounts, title, description, voting_period)
+            process_create_proposal(program_id, accounts, title, description, voting_period, instructions)
         }
-        GovernanceInstruction::CastVote { vote, amount } => {
+        GovernanceInstruction::CastVote { vote } => {
             msg!("Instruction: Cast Vote");
-            process_cast_vote(program_id, accounts, vote, amount)
+            process_cast_vote(program_id, accounts, vote)
         }
         GovernanceInstruction::ExecuteProposal {} => {
             msg!("Instruction: Execute Proposal");
             process_execute_proposal(program_id, accounts)
         }
+        GovernanceInstruction::InitializeGovernance { params, security_council } => {
+            msg!("Instruction: Initialize Governance");
+            process_initialize_governance(program_id, accounts, params, security_council)
+        }
+        GovernanceInstruction::UpdateGovernanceConfig { admin, security_council, params } => {
+            msg!("Instruction: Update Governance Config");
+            process_update_governance_config(program_id, accounts, admin, security_council, params)
+        }
+        GovernanceInstruction::QueueProposal {} => {
+            msg!("Instruction: Queue Proposal");
+            process_queue_proposal(program_id, accounts)
+        }
+        GovernanceInstruction::CancelProposal {} => {
+            msg!("Instruction: Cancel Proposal");
+            process_cancel_proposal(program_id, accounts)
+        }
+        GovernanceInstruction::VetoProposal {} => {
+            msg!("Instruction: Veto Proposal");
+            process_veto_proposal(program_id, accounts)
+        }
     }
 }
 
@@ -84,29 +430,96 @@ fn process_create_proposal(
     title: String,
     description: String,
     voting_period: i64,
+    instructions: Vec<ProposalInstruction>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let creator = next_account_info(account_info_iter)?;
     let proposal_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let stake_pool_account = next_account_info(account_info_iter)?;
+    let stake_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
 
     // Verify creator is signer
     if !creator.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    let mut config = load_governance_config(program_id, config_account)?;
+    let params = config.params;
+    if voting_period < params.min_voting_period
+        || voting_period > params.max_voting_period
+        || instructions.len() > MAX_PROPOSAL_INSTRUCTIONS
+    {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    // Whoever sends the execution must not be able to lend a signature to it
+    let (authority_key, _) = Pubkey::find_program_address(&[GOVERNANCE_AUTHORITY_SEED], program_id);
+    if instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+        .any(|meta| meta.is_signer && meta.pubkey != authority_key)
+    {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let weight = get_voting_power(&config.tola_program, stake_account, creator.key, current_time)?;
+    if weight < params.proposal_threshold {
+        return Err(VortexError::InsufficientFunds.into());
+    }
+
+    // Votes are cast with staked TOLA, so quorum is a share of what is staked
+    // now rather than of the circulating supply, most of which can never vote
+    let total_staked = get_total_staked(&config.tola_program, stake_pool_account)?;
+    let quorum_votes = (total_staked as u128 * params.quorum_bps as u128 / 10_000) as u64;
+
+    let id = config.proposal_count;
+    let id_bytes = id.to_le_bytes();
+    let (proposal_key, proposal_bump) = Pubkey::find_program_address(&[PROPOSAL_SEED, &id_bytes], program_id);
+    if *proposal_account.key != proposal_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
     // Create proposal
+    let start_time = current_time.checked_add(params.voting_delay).ok_or(VortexError::MathOverflow)?;
     let proposal = Proposal {
+        id,
         creator: *creator.key,
         title,
         description,
-        start_time: solana_program::clock::Clock::get()?.unix_timestamp,
-        end_time: solana_program::clock::Clock::get()?.unix_timestamp + voting_period,
+        start_time,
+        end_time: start_time.checked_add(voting_period).ok_or(VortexError::MathOverflow)?,
         yes_votes: 0,
         no_votes: 0,
-        executed: false,
+        state: if params.voting_delay > 0 { ProposalState::Draft } else { ProposalState::Active },
+        eta: 0,
+        params,
+        quorum_votes,
+        bump: proposal_bump,
+        instructions,
     };
+    create_pda_account(
+        creator,
+        proposal_account,
+        system_program,
+        program_id,
+        Proposal::len(&proposal.title, &proposal.description, &proposal.instructions),
+        &proposal.signer_seeds(&id_bytes, &[proposal_bump]),
+    )?;
+    proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+    config.proposal_count = id.checked_add(1).ok_or(VortexError::MathOverflow)?;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ProposalCreated {
+        creator: proposal.creator,
+        proposal_id: proposal_key,
+        title: proposal.title,
+        voting_period,
+    });
 
-    proposal.serialize(&mut *proposal_account.data.borrow_mut())?;
     Ok(())
 }
 
@@ -114,33 +527,77 @@ fn process_cast_vote(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     vote: bool,
-    amount: u64,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let voter = next_account_info(account_info_iter)?;
     let proposal_account = next_account_info(account_info_iter)?;
-    let voter_token_account = next_account_info(account_info_iter)?;
+    let stake_account = next_account_info(account_info_iter)?;
+    let vote_record_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
 
     // Verify voter is signer
     if !voter.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Update vote counts
-    let mut proposal = Proposal::try_from_slice(&proposal_account.data.borrow())?;
-    let current_time = solana_program::clock::Clock::get()?.unix_timestamp;
-    
-    if current_time > proposal.end_time {
-        return Err(ProgramError::InvalidInstructionData);
+    let config = load_governance_config(program_id, config_account)?;
+    let mut proposal = load_proposal(program_id, proposal_account)?;
+    let current_time = Clock::get()?.unix_timestamp;
+    proposal.state = proposal.check_transition(current_time, ProposalState::Active)?;
+
+    // One vote per voter and proposal, enforced by the record's address
+    let (record_key, record_bump) = Pubkey::find_program_address(
+        &[VOTE_RECORD_SEED, proposal_account.key.as_ref(), voter.key.as_ref()],
+        program_id,
+    );
+    if *vote_record_account.key != record_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !vote_record_account.data_is_empty() {
+        return Err(VortexError::AlreadyVoted.into());
     }
 
-    if vote {
-        proposal.yes_votes += amount;
-    } else {
-        proposal.no_votes += amount;
+    // Only stake locked past the end of voting counts: liquid TOLA, or stake
+    // that unlocks mid-vote, could be moved to another wallet and voted again
+    let stake = get_stake(&config.tola_program, stake_account, voter.key)?;
+    if stake.lock_end < proposal.end_time {
+        return Err(VortexError::StakeLockTooShort.into());
+    }
+    let weight = stake.voting_power_at(current_time);
+    if weight == 0 {
+        return Err(VortexError::InsufficientFunds.into());
     }
 
-    proposal.serialize(&mut *proposal_account.data.borrow_mut())?;
+    // Update vote counts
+    let tally = if vote { &mut proposal.yes_votes } else { &mut proposal.no_votes };
+    *tally = tally.checked_add(weight).ok_or(VortexError::MathOverflow)?;
+    proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+    create_pda_account(
+        voter,
+        vote_record_account,
+        system_program,
+        program_id,
+        VoteRecord::LEN,
+        &[VOTE_RECORD_SEED, proposal_account.key.as_ref(), voter.key.as_ref(), &[record_bump]],
+    )?;
+    let record = VoteRecord {
+        proposal: *proposal_account.key,
+        voter: *voter.key,
+        vote,
+        weight,
+        bump: record_bump,
+    };
+    record.serialize(&mut &mut vote_record_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::VoteCast {
+        voter: *voter.key,
+        proposal_id: *proposal_account.key,
+        amount: weight,
+        vote,
+    });
+
     Ok(())
 }
 
@@ -149,33 +606,248 @@ fn process_execute_proposal(
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let executor = next_account_info(account_info_iter)?;
     let proposal_account = next_account_info(account_info_iter)?;
+    let governance_authority = next_account_info(account_info_iter)?;
+
+    let mut proposal = load_proposal(program_id, proposal_account)?;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Only proposals that passed get queued, so the outcome needs no second look
+    proposal.check_transition(current_time, ProposalState::Executed)?;
+
+    let (authority_key, authority_bump) = Pubkey::find_program_address(&[GOVERNANCE_AUTHORITY_SEED], program_id);
+    if *governance_authority.key != authority_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Mark executed before running anything so no instruction can re-enter it
+    proposal.state = ProposalState::Executed;
+    proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+    for instruction in &proposal.instructions {
+        invoke_signed(
+            &Instruction::from(instruction),
+            accounts,
+            &[&[GOVERNANCE_AUTHORITY_SEED, &[authority_bump]]],
+        )?;
+    }
+
+    emit(VortexEvent::ProposalExecuted {
+        proposal_id: *proposal_account.key,
+        yes_votes: proposal.yes_votes,
+        no_votes: proposal.no_votes,
+    });
+
+    Ok(())
+}
+
+fn process_queue_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let caller = next_account_info(account_info_iter)?;
+    let proposal_account = next_account_info(account_info_iter)?;
+
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Judged by the parameters snapshotted at creation
+    let mut proposal = load_proposal(program_id, proposal_account)?;
+    proposal.check_transition(Clock::get()?.unix_timestamp, ProposalState::Queued)?;
+
+    proposal.state = ProposalState::Queued;
+    proposal.eta = proposal
+        .end_time
+        .checked_add(proposal.params.timelock_delay)
+        .ok_or(VortexError::MathOverflow)?;
+    proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ProposalQueued {
+        proposal_id: *proposal_account.key,
+        eta: proposal.eta,
+    });
+
+    Ok(())
+}
+
+fn process_cancel_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let creator = next_account_info(account_info_iter)?;
+    let proposal_account = next_account_info(account_info_iter)?;
+
+    if !creator.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut proposal = load_proposal(program_id, proposal_account)?;
+    if proposal.creator != *creator.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    proposal.check_transition(Clock::get()?.unix_timestamp, ProposalState::Cancelled)?;
+
+    proposal.state = ProposalState::Cancelled;
+    proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ProposalCancelled {
+        proposal_id: *proposal_account.key,
+    });
+
+    Ok(())
+}
+
+fn process_veto_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let council = next_account_info(account_info_iter)?;
+    let proposal_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
 
-    // Verify executor is signer
-    if !executor.is_signer {
+    if !council.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Execute proposal
-    let mut proposal = Proposal::try_from_slice(&proposal_account.data.borrow())?;
-    let current_time = solana_program::clock::Clock::get()?.unix_timestamp;
-    
-    if current_time <= proposal.end_time {
-        return Err(ProgramError::InvalidInstructionData);
+    let config = load_governance_config(program_id, config_account)?;
+    if config.security_council == Pubkey::default() || config.security_council != *council.key {
+        return Err(VortexError::Unauthorized.into());
     }
+    let mut proposal = load_proposal(program_id, proposal_account)?;
+    proposal.check_transition(Clock::get()?.unix_timestamp, ProposalState::Vetoed)?;
+
+    proposal.state = ProposalState::Vetoed;
+    proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ProposalVetoed {
+        proposal_id: *proposal_account.key,
+        security_council: *council.key,
+    });
+
+    Ok(())
+}
 
-    if proposal.executed {
-        return Err(ProgramError::InvalidAccountData);
+fn load_proposal(program_id: &Pubkey, proposal_account: &AccountInfo) -> Result<Proposal, ProgramError> {
+    if proposal_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let proposal = Proposal::try_from_slice(&proposal_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(
+        &proposal.signer_seeds(&proposal.id.to_le_bytes(), &[proposal.bump]),
+        program_id,
+    )?;
+    if expected != *proposal_account.key {
+        return Err(VortexError::InvalidAccount.into());
     }
+    Ok(proposal)
+}
 
-    // Check if proposal passed
-    if proposal.yes_votes > proposal.no_votes {
-        proposal.executed = true;
-        proposal.serialize(&mut *proposal_account.data.borrow_mut())?;
-    } else {
-        return Err(ProgramError::InvalidAccountData);
+fn load_governance_config(program_id: &Pubkey, config_account: &AccountInfo) -> Result<GovernanceConfig, ProgramError> {
+    if config_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let config = GovernanceConfig::try_from_slice(&config_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(&[GOVERNANCE_CONFIG_SEED, &[config.bump]], program_id)?;
+    if expected != *config_account.key {
+        return Err(VortexError::InvalidAccount.into());
     }
+    Ok(config)
+}
+
+fn process_initialize_governance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: GovernanceParams,
+    security_council: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let tola_state_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    params.validate()?;
+
+    // The state must be the genuine PDA of the pinned TOLA program; any
+    // other owner could fake the admin
+    let tola_program = &token::ID;
+    if tola_state_account.owner != tola_program {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let tola_state = TolaState::try_from_slice(&tola_state_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(&[STATE_SEED, &[tola_state.bump]], tola_program)?;
+    if expected != *tola_state_account.key || !tola_state.initialized {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if tola_state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+
+    let (config_key, config_bump) = Pubkey::find_program_address(&[GOVERNANCE_CONFIG_SEED], program_id);
+    if *config_account.key != config_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !config_account.data_is_empty() {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+
+    create_pda_account(
+        admin,
+        config_account,
+        system_program,
+        program_id,
+        GovernanceConfig::LEN,
+        &[GOVERNANCE_CONFIG_SEED, &[config_bump]],
+    )?;
+    let config = GovernanceConfig {
+        admin: *admin.key,
+        security_council,
+        tola_program: *tola_program,
+        params,
+        proposal_count: 0,
+        bump: config_bump,
+    };
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::GovernanceConfigUpdated {
+        admin: config.admin,
+        quorum_bps: params.quorum_bps,
+        approval_threshold_bps: params.approval_threshold_bps,
+        proposal_threshold: params.proposal_threshold,
+    });
+
+    Ok(())
+}
+
+fn process_update_governance_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    admin: Pubkey,
+    security_council: Pubkey,
+    params: GovernanceParams,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut config = load_governance_config(program_id, config_account)?;
+    if config.admin != *signer.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    params.validate()?;
+
+    config.admin = admin;
+    config.security_council = security_council;
+    config.params = params;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::GovernanceConfigUpdated {
+        admin,
+        quorum_bps: params.quorum_bps,
+        approval_threshold_bps: params.approval_threshold_bps,
+        proposal_threshold: params.proposal_threshold,
+    });
 
     Ok(())
-} 
\ No newline at end of file
+}
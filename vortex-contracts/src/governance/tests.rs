@@ -0,0 +1,959 @@
+use super::*;
+use crate::tests::helpers::*;
+use crate::marketplace::{MarketplaceConfig, MarketplaceInstruction, CONFIG_SEED};
+use crate::token::{
+    StakeAccount, StakePool, TolaInstruction, MAX_STAKE_DURATION, MINT_SEED, REWARD_SCHEDULE_SEED, REWARD_VAULT_SEED,
+    STAKE_POOL_SEED, STAKE_SEED, STAKE_VAULT_SEED, TREASURY_SEED,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    rent::Rent,
+    system_instruction,
+    system_program,
+    sysvar,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    signature::{Keypair, Signer},
+};
+
+fn program_test(program_id: Pubkey) -> ProgramTest {
+    ProgramTest::new(
+        "vortex_contracts",
+        program_id,
+        processor!(process_instruction),
+    )
+}
+
+fn authority_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[GOVERNANCE_AUTHORITY_SEED], program_id).0
+}
+
+fn config_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[GOVERNANCE_CONFIG_SEED], program_id).0
+}
+
+fn vote_record_address(program_id: &Pubkey, proposal: &Pubkey, voter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[VOTE_RECORD_SEED, proposal.as_ref(), voter.as_ref()], program_id).0
+}
+
+fn proposal_address(program_id: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[PROPOSAL_SEED, &id.to_le_bytes()], program_id).0
+}
+
+// 10% quorum, 60% approval, voting for a minute to a week, 1,000 TOLA to propose
+fn default_params() -> GovernanceParams {
+    GovernanceParams {
+        quorum_bps: 1_000,
+        approval_threshold_bps: 6_000,
+        min_voting_period: 60,
+        max_voting_period: 7 * 24 * 60 * 60,
+        proposal_threshold: 1_000,
+        timelock_delay: 24 * 60 * 60,
+        grace_period: 3 * 24 * 60 * 60,
+        voting_delay: 0,
+    }
+}
+
+fn initialize_governance_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    tola_state: &Pubkey,
+    params: GovernanceParams,
+) -> Instruction {
+    // No security council until a test names one
+    let security_council = Pubkey::default();
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::InitializeGovernance { params, security_council }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_address(program_id), false),
+            AccountMeta::new_readonly(*tola_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn update_governance_config_instruction(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    admin: &Pubkey,
+    security_council: &Pubkey,
+    params: GovernanceParams,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::UpdateGovernanceConfig { admin: *admin, security_council: *security_council, params }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(config_address(program_id), false),
+        ],
+    )
+}
+
+fn create_proposal_instruction(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    id: u64,
+    stake_pool: &Pubkey,
+    creator_stake: &Pubkey,
+    voting_period: i64,
+    instructions: &[Instruction],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::CreateProposal {
+            title: "Lower fees".to_string(),
+            description: "Cut the platform fee to 2%".to_string(),
+            voting_period,
+            instructions: instructions.iter().map(ProposalInstruction::from).collect(),
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*creator, true),
+            AccountMeta::new(proposal_address(program_id, id), false),
+            AccountMeta::new(config_address(program_id), false),
+            AccountMeta::new_readonly(*stake_pool, false),
+            AccountMeta::new_readonly(*creator_stake, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn queue_proposal_instruction(program_id: &Pubkey, caller: &Pubkey, proposal: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::QueueProposal {}.try_to_vec().unwrap(),
+        vec![AccountMeta::new_readonly(*caller, true), AccountMeta::new(*proposal, false)],
+    )
+}
+
+fn cancel_proposal_instruction(program_id: &Pubkey, creator: &Pubkey, proposal: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::CancelProposal {}.try_to_vec().unwrap(),
+        vec![AccountMeta::new_readonly(*creator, true), AccountMeta::new(*proposal, false)],
+    )
+}
+
+fn veto_proposal_instruction(program_id: &Pubkey, council: &Pubkey, proposal: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::VetoProposal {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*council, true),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+        ],
+    )
+}
+
+fn execute_proposal_instruction(
+    program_id: &Pubkey,
+    proposal: &Pubkey,
+    remaining_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*proposal, false),
+        AccountMeta::new_readonly(authority_address(program_id), false),
+    ];
+    accounts.extend_from_slice(remaining_accounts);
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::ExecuteProposal {}.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+fn cast_vote_instruction(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    proposal: &Pubkey,
+    stake_account: &Pubkey,
+    vote: bool,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GovernanceInstruction::CastVote { vote }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*voter, true),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(*stake_account, false),
+            AccountMeta::new(vote_record_address(program_id, proposal, voter), false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn set_borsh_account<T: BorshSerialize>(context: &mut ProgramTestContext, address: &Pubkey, owner: &Pubkey, state: &T) {
+    let account = Account {
+        lamports: 1_000_000_000,
+        data: state.try_to_vec().unwrap(),
+        owner: *owner,
+        ..Account::default()
+    };
+    context.set_account(address, &AccountSharedData::from(account));
+}
+
+// Stand-in for an initialized TOLA token program and its empty stake pool, returning its state and mint
+fn create_tola(context: &mut ProgramTestContext, tola_program: &Pubkey, admin: &Pubkey) -> (Pubkey, Pubkey) {
+    let (state_key, bump) = Pubkey::find_program_address(&[STATE_SEED], tola_program);
+    let mint = Pubkey::new_unique();
+    set_packed_account(
+        context,
+        &mint,
+        spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: 1_000_000,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+    );
+    let state = TolaState {
+        total_supply: 1_000_000,
+        mint_authority: *admin,
+        initialized: true,
+        mint,
+        treasury: Pubkey::new_unique(),
+        bump,
+        admin: *admin,
+        paused: false,
+        total_burned: 0,
+        circulating_supply: 1_000_000,
+        max_supply: 1_000_000,
+        minting_renounced: false,
+    };
+    set_borsh_account(context, &state_key, tola_program, &state);
+    let (pool_key, pool_bump) = Pubkey::find_program_address(&[STAKE_POOL_SEED], tola_program);
+    let pool = StakePool {
+        total_staked: 0,
+        reward_per_token_stored: 0,
+        last_update_timestamp: 0,
+        stake_vault: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        bump: pool_bump,
+        reward_schedule: Pubkey::new_unique(),
+    };
+    set_borsh_account(context, &pool_key, tola_program, &pool);
+    (state_key, mint)
+}
+
+fn tola_address(seed: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[seed], &token::ID).0
+}
+
+// Initialize the real TOLA token program, with its stake pool, minting `total_supply` to the treasury
+async fn initialize_tola(context: &mut ProgramTestContext, total_supply: u64) {
+    let admin = context.payer.pubkey();
+    set_upgrade_authority(context, &token::ID, &admin);
+    let initialize = Instruction::new_with_bytes(
+        token::ID,
+        &TolaInstruction::Initialize { total_supply, max_supply: total_supply }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(admin, true),
+            AccountMeta::new(tola_address(MINT_SEED), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(tola_address(STATE_SEED), false),
+            AccountMeta::new(tola_address(TREASURY_SEED), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(program_data_address(&token::ID), false),
+        ],
+    );
+    let initialize_stake_pool = Instruction::new_with_bytes(
+        token::ID,
+        &TolaInstruction::InitializeStakePool {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(admin, true),
+            AccountMeta::new(tola_address(STAKE_POOL_SEED), false),
+            AccountMeta::new(tola_address(STAKE_VAULT_SEED), false),
+            AccountMeta::new(tola_address(REWARD_VAULT_SEED), false),
+            AccountMeta::new_readonly(tola_address(STATE_SEED), false),
+            AccountMeta::new_readonly(tola_address(MINT_SEED), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(tola_address(REWARD_SCHEDULE_SEED), false),
+        ],
+    );
+    process(context, &[initialize, initialize_stake_pool], &[]).await.unwrap();
+}
+
+// Write a stake straight into the TOLA program, counting it in the stake pool
+async fn create_stake(
+    context: &mut ProgramTestContext,
+    tola_program: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    lock_end: i64,
+) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(&[STAKE_SEED, owner.as_ref()], tola_program);
+    let stake = StakeAccount {
+        owner: *owner,
+        amount,
+        start_timestamp: now(context).await,
+        lock_end,
+        reward_per_token_paid: 0,
+        pending_rewards: 0,
+        bump,
+        voting_power: 0,
+    };
+    set_borsh_account(context, &address, tola_program, &stake);
+
+    let pool_key = Pubkey::find_program_address(&[STAKE_POOL_SEED], tola_program).0;
+    let account = context.banks_client.get_account(pool_key).await.unwrap().unwrap();
+    let mut pool = StakePool::try_from_slice(&account.data).unwrap();
+    pool.total_staked += amount;
+    set_borsh_account(context, &pool_key, tola_program, &pool);
+    address
+}
+
+// A stake locked for the longest term, so it votes with its full amount until the clock moves
+async fn create_full_stake(
+    context: &mut ProgramTestContext,
+    tola_program: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let lock_end = now(context).await + MAX_STAKE_DURATION;
+    create_stake(context, tola_program, owner, amount, lock_end).await
+}
+
+async fn initialize_governance(context: &mut ProgramTestContext, program_id: &Pubkey, tola_state: &Pubkey) {
+    let admin = context.payer.pubkey();
+    process(
+        context,
+        &[initialize_governance_instruction(program_id, &admin, tola_state, default_params())],
+        &[],
+    )
+    .await
+    .unwrap();
+}
+
+async fn config(context: &mut ProgramTestContext, program_id: &Pubkey) -> GovernanceConfig {
+    let account = context.banks_client.get_account(config_address(program_id)).await.unwrap().unwrap();
+    GovernanceConfig::try_from_slice(&account.data).unwrap()
+}
+
+// Create the next proposal on behalf of a creator with enough TOLA staked
+async fn create_proposal(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    creator: &Keypair,
+    creator_stake: &Pubkey,
+    voting_period: i64,
+) -> Pubkey {
+    let id = config(context, program_id).await.proposal_count;
+    process(
+        context,
+        &[create_proposal_instruction(
+            program_id,
+            &creator.pubkey(),
+            id,
+            stake_pool,
+            creator_stake,
+            voting_period,
+            &[],
+        )],
+        &[creator],
+    )
+    .await
+    .unwrap();
+    proposal_address(program_id, id)
+}
+
+async fn proposal(context: &mut ProgramTestContext, address: &Pubkey) -> Proposal {
+    let account = context.banks_client.get_account(*address).await.unwrap().unwrap();
+    Proposal::try_from_slice(&account.data).unwrap()
+}
+
+async fn now(context: &mut ProgramTestContext) -> i64 {
+    clock(context).await.unix_timestamp
+}
+
+#[tokio::test]
+async fn test_initialize_governance_requires_tola_admin() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, _) = create_tola(&mut context, &tola_program, &admin);
+
+    let impostor = Keypair::new();
+    let err = process(
+        &mut context,
+        &[initialize_governance_instruction(&program_id, &impostor.pubkey(), &tola_state, default_params())],
+        &[&impostor],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+
+    // The state of a token program other than the pinned one is refused
+    let (other_state, _) = create_tola(&mut context, &Pubkey::new_unique(), &admin);
+    let initialize = initialize_governance_instruction(&program_id, &admin, &other_state, default_params());
+    let err = process(&mut context, &[initialize], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    // A look-alike state at a non-PDA address is refused
+    let forged = Pubkey::new_unique();
+    let account = context.banks_client.get_account(tola_state).await.unwrap().unwrap();
+    context.set_account(&forged, &AccountSharedData::from(account));
+    let initialize = initialize_governance_instruction(&program_id, &admin, &forged, default_params());
+    let err = process(&mut context, &[initialize], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let config = config(&mut context, &program_id).await;
+    assert_eq!(config.admin, admin);
+    assert_eq!(config.params, default_params());
+    assert_eq!(config.tola_program, tola_program);
+}
+
+#[tokio::test]
+async fn test_cast_vote_weighs_locked_stake_once() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, _) = create_tola(&mut context, &tola_program, &admin);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    let holder = Keypair::new();
+    let staker = Keypair::new();
+    let holder_stake = create_full_stake(&mut context, &tola_program, &holder.pubkey(), 1_000).await;
+    let now = now(&mut context).await;
+    let stake = create_stake(&mut context, &tola_program, &staker.pubkey(), 4_000, now + MAX_STAKE_DURATION / 2).await;
+    fund_lamports(&mut context, &holder.pubkey()).await;
+    fund_lamports(&mut context, &staker.pubkey()).await;
+    let proposal_key = create_proposal(&mut context, &program_id, &stake_pool, &holder, &holder_stake, 3_600).await;
+
+    // A stake account belonging to another voter is refused
+    let err = process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &holder.pubkey(), &proposal_key, &stake, true)],
+        &[&holder],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &holder.pubkey(), &proposal_key, &holder_stake, true)],
+        &[&holder],
+    )
+    .await
+    .unwrap();
+
+    // Half the lock remains, so the stake counts for half its amount
+    process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &staker.pubkey(), &proposal_key, &stake, false)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let tallied = proposal(&mut context, &proposal_key).await;
+    assert_eq!((tallied.yes_votes, tallied.no_votes), (1_000, 2_000));
+
+    let record_key = vote_record_address(&program_id, &proposal_key, &staker.pubkey());
+    let account = context.banks_client.get_account(record_key).await.unwrap().unwrap();
+    let record = VoteRecord::try_from_slice(&account.data).unwrap();
+    assert_eq!((record.voter, record.vote, record.weight), (staker.pubkey(), false, 2_000));
+
+    // Voting again, even the other way, is rejected
+    let err = process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &holder.pubkey(), &proposal_key, &holder_stake, false)],
+        &[&holder],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::AlreadyVoted);
+
+    // Voters with nothing to weigh are turned away
+    let empty = Keypair::new();
+    let empty_stake = create_full_stake(&mut context, &tola_program, &empty.pubkey(), 0).await;
+    fund_lamports(&mut context, &empty.pubkey()).await;
+    let err = process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &empty.pubkey(), &proposal_key, &empty_stake, true)],
+        &[&empty],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn test_moved_tokens_do_not_vote_twice() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, tola_mint) = create_tola(&mut context, &tola_program, &admin);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    // The voter holds 10,000 TOLA on top of a 4,000 stake
+    let voter = Keypair::new();
+    let fresh = Keypair::new();
+    let voter_tokens = create_token_account(&mut context, &tola_mint, &voter.pubkey(), 10_000);
+    let fresh_tokens = create_token_account(&mut context, &tola_mint, &fresh.pubkey(), 0);
+    let voter_stake = create_full_stake(&mut context, &tola_program, &voter.pubkey(), 4_000).await;
+    fund_lamports(&mut context, &voter.pubkey()).await;
+    fund_lamports(&mut context, &fresh.pubkey()).await;
+    let proposal_key = create_proposal(&mut context, &program_id, &stake_pool, &voter, &voter_stake, 3_600).await;
+
+    // Only the stake counts
+    process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &voter.pubkey(), &proposal_key, &voter_stake, true)],
+        &[&voter],
+    )
+    .await
+    .unwrap();
+    assert_eq!(proposal(&mut context, &proposal_key).await.yes_votes, 4_000);
+
+    // Handing the liquid TOLA to a fresh wallet gives it nothing to vote with
+    let transfer =
+        spl_token::instruction::transfer(&spl_token::id(), &voter_tokens, &fresh_tokens, &voter.pubkey(), &[], 10_000)
+            .unwrap();
+    process(&mut context, &[transfer], &[&voter]).await.unwrap();
+    let fresh_stake = Pubkey::find_program_address(&[STAKE_SEED, fresh.pubkey().as_ref()], &tola_program).0;
+    let err = process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &fresh.pubkey(), &proposal_key, &fresh_stake, true)],
+        &[&fresh],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    // Nor can it vote with a stake that unlocks, and could move again, before voting ends
+    let end_time = proposal(&mut context, &proposal_key).await.end_time;
+    let fresh_stake = create_stake(&mut context, &tola_program, &fresh.pubkey(), 10_000, end_time - 1).await;
+    let err = process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &fresh.pubkey(), &proposal_key, &fresh_stake, true)],
+        &[&fresh],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::StakeLockTooShort);
+    assert_eq!(proposal(&mut context, &proposal_key).await.yes_votes, 4_000);
+}
+
+#[tokio::test]
+async fn test_proposal_thresholds_use_snapshotted_params() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, _) = create_tola(&mut context, &tola_program, &admin);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    // Stakers of 6%, 3%, 2% and 0.05% of the 1,000,000 TOLA staked, the rest by a bystander
+    create_full_stake(&mut context, &tola_program, &Pubkey::new_unique(), 889_500).await;
+    let mut voters = Vec::new();
+    for amount in [60_000, 30_000, 20_000, 500] {
+        let voter = Keypair::new();
+        let stake = create_full_stake(&mut context, &tola_program, &voter.pubkey(), amount).await;
+        fund_lamports(&mut context, &voter.pubkey()).await;
+        voters.push((voter, stake));
+    }
+    let (whale, whale_stake) = &voters[0];
+    let (minnow, minnow_stake) = &voters[3];
+
+    // Voting periods outside the configured bounds are refused
+    for voting_period in [59, 7 * 24 * 60 * 60 + 1] {
+        let create =
+            create_proposal_instruction(&program_id, &whale.pubkey(), 0, &stake_pool, whale_stake, voting_period, &[]);
+        let err = process(&mut context, &[create], &[whale]).await.unwrap_err().unwrap();
+        expect_error(err, VortexError::InvalidInstruction);
+    }
+
+    // Stakers below the creation threshold cannot propose
+    let create = create_proposal_instruction(&program_id, &minnow.pubkey(), 0, &stake_pool, minnow_stake, 3_600, &[]);
+    let err = process(&mut context, &[create], &[minnow]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InsufficientFunds);
+
+    let start = now(&mut context).await;
+    let mut proposals = Vec::new();
+    for _ in 0..3 {
+        proposals.push(create_proposal(&mut context, &program_id, &stake_pool, whale, whale_stake, 3_600).await);
+    }
+    let created = proposal(&mut context, &proposals[0]).await;
+    assert_eq!((created.id, created.quorum_votes), (0, 100_000));
+    assert_eq!(created.params, default_params());
+    assert_eq!(config(&mut context, &program_id).await.proposal_count, 3);
+
+    // Below quorum: 6% yes
+    let vote = |voter: usize, proposal: &Pubkey, vote: bool| {
+        cast_vote_instruction(&program_id, &voters[voter].0.pubkey(), proposal, &voters[voter].1, vote)
+    };
+    process(&mut context, &[vote(0, &proposals[0], true)], &[whale]).await.unwrap();
+    // Quorum but short of approval: 6% yes against 3% + 2% no is 54.5%
+    for (voter, choice) in [(0, true), (1, false), (2, false)] {
+        process(&mut context, &[vote(voter, &proposals[1], choice)], &[&voters[voter].0]).await.unwrap();
+    }
+    // Quorum and approval: 6% + 2% yes against 3% no is 72.7%
+    for (voter, choice) in [(0, true), (1, false), (2, true)] {
+        process(&mut context, &[vote(voter, &proposals[2], choice)], &[&voters[voter].0]).await.unwrap();
+    }
+
+    // Loosening the rules later does not rescue existing proposals
+    let loose = GovernanceParams { quorum_bps: 0, approval_threshold_bps: 1, ..default_params() };
+    let err = process(
+        &mut context,
+        &[update_governance_config_instruction(&program_id, &whale.pubkey(), &whale.pubkey(), &admin, loose)],
+        &[whale],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+    let update = update_governance_config_instruction(&program_id, &admin, &admin, &Pubkey::default(), loose);
+    process(&mut context, &[update], &[])
+        .await
+        .unwrap();
+    assert_eq!(config(&mut context, &program_id).await.params, loose);
+
+    set_clock(&mut context, start + 3_601).await;
+    let queue = |proposal: &Pubkey| queue_proposal_instruction(&program_id, &admin, proposal);
+    let err = process(&mut context, &[queue(&proposals[0])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::QuorumNotReached);
+    let err = process(&mut context, &[queue(&proposals[1])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ApprovalThresholdNotMet);
+    process(&mut context, &[queue(&proposals[2])], &[]).await.unwrap();
+
+    let eta = proposal(&mut context, &proposals[2]).await.eta;
+    set_clock(&mut context, eta).await;
+    process(&mut context, &[execute_proposal_instruction(&program_id, &proposals[2], &[])], &[])
+        .await
+        .unwrap();
+    assert_eq!(proposal(&mut context, &proposals[2]).await.state, ProposalState::Executed);
+}
+
+#[tokio::test]
+async fn test_execute_proposal_invokes_instructions_as_governance() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let marketplace_id = Pubkey::new_unique();
+    let mut test = program_test(program_id);
+    test.add_program("vortex_contracts", marketplace_id, processor!(crate::marketplace::process_instruction));
+    test.add_program("vortex_contracts", tola_program, processor!(crate::token::process_instruction));
+    let mut context = test.start_with_context().await;
+    let admin = context.payer.pubkey();
+    initialize_tola(&mut context, 1_000_000).await;
+    let tola_state = tola_address(STATE_SEED);
+    let tola_mint = tola_address(MINT_SEED);
+    let treasury = tola_address(TREASURY_SEED);
+    let reward_vault = tola_address(REWARD_VAULT_SEED);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    // The TOLA admin hands the treasury, held by the token program's state PDA, over to governance
+    let authority = authority_address(&program_id);
+    let set_admin = Instruction::new_with_bytes(
+        tola_program,
+        &TolaInstruction::SetAdmin { new_admin: authority }.try_to_vec().unwrap(),
+        vec![AccountMeta::new_readonly(admin, true), AccountMeta::new(tola_state, false)],
+    );
+    process(&mut context, &[set_admin], &[]).await.unwrap();
+
+    // The marketplace is administered by the governance authority too
+    let (market_config, market_bump) = Pubkey::find_program_address(&[CONFIG_SEED], &marketplace_id);
+    let fee_recipient = Pubkey::new_unique();
+    let market = MarketplaceConfig {
+        admin: authority,
+        fee_bps: 500,
+        fee_recipient,
+        max_royalty_percentage: 10,
+        paused: false,
+        bump: market_bump,
+        accepted_mints: vec![tola_mint],
+    };
+    set_borsh_account(&mut context, &market_config, &marketplace_id, &market);
+
+    let lower_fees = Instruction::new_with_bytes(
+        marketplace_id,
+        &MarketplaceInstruction::UpdateConfig {
+            admin: authority,
+            fee_bps: 200,
+            fee_recipient,
+            max_royalty_percentage: 10,
+            accepted_mints: vec![tola_mint],
+            paused: false,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(authority, true),
+            AccountMeta::new(market_config, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    );
+    let fund_rewards = Instruction::new_with_bytes(
+        tola_program,
+        &TolaInstruction::FundRewards { amount: 1_000 }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(tola_address(STAKE_POOL_SEED), false),
+            AccountMeta::new(reward_vault, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(tola_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    );
+
+    let voter = Keypair::new();
+    let voter_stake = create_full_stake(&mut context, &tola_program, &voter.pubkey(), 120_000).await;
+    fund_lamports(&mut context, &voter.pubkey()).await;
+    let create = |instructions: &[Instruction]| {
+        create_proposal_instruction(&program_id, &voter.pubkey(), 0, &stake_pool, &voter_stake, 3_600, instructions)
+    };
+
+    // Proposals carry a bounded number of instructions
+    let too_many = vec![fund_rewards.clone(); MAX_PROPOSAL_INSTRUCTIONS + 1];
+    let err = process(&mut context, &[create(&too_many)], &[&voter]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidInstruction);
+
+    // Nor may they need any signer but the governance authority, such as whoever executes them
+    let victim = Keypair::new();
+    let victim_tokens = create_token_account(&mut context, &tola_mint, &victim.pubkey(), 5_000);
+    let drain =
+        spl_token::instruction::transfer(&spl_token::id(), &victim_tokens, &treasury, &victim.pubkey(), &[], 5_000)
+            .unwrap();
+    let err = process(&mut context, &[create(&[drain])], &[&voter]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidInstruction);
+
+    let start = now(&mut context).await;
+    let instructions = [lower_fees, fund_rewards];
+    process(&mut context, &[create(&instructions)], &[&voter]).await.unwrap();
+    let proposal_key = proposal_address(&program_id, 0);
+    let stored = proposal(&mut context, &proposal_key).await;
+    assert_eq!(stored.instructions, instructions.iter().map(ProposalInstruction::from).collect::<Vec<_>>());
+
+    process(
+        &mut context,
+        &[cast_vote_instruction(&program_id, &voter.pubkey(), &proposal_key, &voter_stake, true)],
+        &[&voter],
+    )
+    .await
+    .unwrap();
+    set_clock(&mut context, start + 3_601).await;
+    process(&mut context, &[queue_proposal_instruction(&program_id, &admin, &proposal_key)], &[]).await.unwrap();
+    let eta = proposal(&mut context, &proposal_key).await.eta;
+    set_clock(&mut context, eta).await;
+
+    // Anyone can execute without signing anything
+    let remaining_accounts = [
+        AccountMeta::new(authority, false),
+        AccountMeta::new_readonly(marketplace_id, false),
+        AccountMeta::new(market_config, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(tola_program, false),
+        AccountMeta::new_readonly(tola_address(STAKE_POOL_SEED), false),
+        AccountMeta::new(reward_vault, false),
+        AccountMeta::new(treasury, false),
+        AccountMeta::new_readonly(tola_state, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    let execute = execute_proposal_instruction(&program_id, &proposal_key, &remaining_accounts);
+    process(&mut context, std::slice::from_ref(&execute), &[]).await.unwrap();
+    assert_eq!(proposal(&mut context, &proposal_key).await.state, ProposalState::Executed);
+
+    let account = context.banks_client.get_account(market_config).await.unwrap().unwrap();
+    assert_eq!(MarketplaceConfig::try_from_slice(&account.data).unwrap().fee_bps, 200);
+    assert_eq!(token_balance(&mut context, &treasury).await, 999_000);
+    assert_eq!(token_balance(&mut context, &reward_vault).await, 1_000);
+
+    // A proposal runs at most once
+    context.get_new_latest_blockhash().await.unwrap();
+    let err = process(&mut context, &[execute], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ProposalAlreadyExecuted);
+}
+
+#[tokio::test]
+async fn test_timelock_bounds_execution() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, _) = create_tola(&mut context, &tola_program, &admin);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    let voter = Keypair::new();
+    let voter_stake = create_full_stake(&mut context, &tola_program, &voter.pubkey(), 120_000).await;
+    fund_lamports(&mut context, &voter.pubkey()).await;
+    let mut proposals = Vec::new();
+    for _ in 0..2 {
+        let proposal_key = create_proposal(&mut context, &program_id, &stake_pool, &voter, &voter_stake, 3_600).await;
+        process(
+            &mut context,
+            &[cast_vote_instruction(&program_id, &voter.pubkey(), &proposal_key, &voter_stake, true)],
+            &[&voter],
+        )
+        .await
+        .unwrap();
+        proposals.push(proposal_key);
+    }
+    let queue = |proposal: &Pubkey| queue_proposal_instruction(&program_id, &admin, proposal);
+    let execute = |proposal: &Pubkey| execute_proposal_instruction(&program_id, proposal, &[]);
+
+    // Nothing is queued while voting is open
+    let err = process(&mut context, &[queue(&proposals[0])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::VotingInProgress);
+
+    let end_time = proposal(&mut context, &proposals[0]).await.end_time;
+    set_clock(&mut context, end_time + 1).await;
+    let err = process(&mut context, &[execute(&proposals[0])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ProposalNotQueued);
+
+    for proposal_key in &proposals {
+        process(&mut context, &[queue(proposal_key)], &[]).await.unwrap();
+    }
+    let queued = proposal(&mut context, &proposals[0]).await;
+    assert_eq!(queued.eta, end_time + default_params().timelock_delay);
+    let err = process(&mut context, &[queue(&proposals[0])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ProposalAlreadyQueued);
+
+    // The delay has to run out first
+    set_clock(&mut context, queued.eta - 1).await;
+    let err = process(&mut context, &[execute(&proposals[0])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::TimelockActive);
+
+    // The last moment of the grace period still counts, the next one does not
+    let deadline = queued.eta + default_params().grace_period;
+    set_clock(&mut context, deadline).await;
+    process(&mut context, &[execute(&proposals[0])], &[]).await.unwrap();
+    set_clock(&mut context, deadline + 1).await;
+    let err = process(&mut context, &[execute(&proposals[1])], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ProposalExpired);
+    let expired = proposal(&mut context, &proposals[1]).await;
+    assert_eq!(expired.state_at(deadline + 1), ProposalState::Expired);
+}
+
+#[tokio::test]
+async fn test_cancel_and_veto_follow_state_machine() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, _) = create_tola(&mut context, &tola_program, &admin);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    let council = Keypair::new();
+    let params = GovernanceParams { voting_delay: 600, ..default_params() };
+    let update = update_governance_config_instruction(&program_id, &admin, &admin, &council.pubkey(), params);
+    process(&mut context, &[update], &[]).await.unwrap();
+    assert_eq!(config(&mut context, &program_id).await.security_council, council.pubkey());
+
+    let creator = Keypair::new();
+    let creator_stake = create_full_stake(&mut context, &tola_program, &creator.pubkey(), 120_000).await;
+    fund_lamports(&mut context, &creator.pubkey()).await;
+    let mut proposals = Vec::new();
+    for _ in 0..3 {
+        proposals.push(create_proposal(&mut context, &program_id, &stake_pool, &creator, &creator_stake, 3_600).await);
+    }
+    let vote =
+        |proposal: &Pubkey| cast_vote_instruction(&program_id, &creator.pubkey(), proposal, &creator_stake, true);
+    let cancel = |proposal: &Pubkey| cancel_proposal_instruction(&program_id, &creator.pubkey(), proposal);
+    let veto = |proposal: &Pubkey| veto_proposal_instruction(&program_id, &council.pubkey(), proposal);
+    let queue = |proposal: &Pubkey| queue_proposal_instruction(&program_id, &admin, proposal);
+
+    // Voting opens only after the delay
+    let draft = proposal(&mut context, &proposals[0]).await;
+    assert_eq!(draft.state, ProposalState::Draft);
+    let err = process(&mut context, &[vote(&proposals[0])], &[&creator]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::VotingNotStarted);
+
+    // Only the creator may cancel, and a cancelled proposal stays cancelled
+    let err = process(&mut context, &[cancel_proposal_instruction(&program_id, &admin, &proposals[0])], &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+    process(&mut context, &[cancel(&proposals[0])], &[&creator]).await.unwrap();
+    assert_eq!(proposal(&mut context, &proposals[0]).await.state, ProposalState::Cancelled);
+    set_clock(&mut context, draft.start_time).await;
+    let err = process(&mut context, &[vote(&proposals[0])], &[&creator]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ProposalCancelled);
+
+    process(&mut context, &[vote(&proposals[1])], &[&creator]).await.unwrap();
+    assert_eq!(proposal(&mut context, &proposals[1]).await.state, ProposalState::Active);
+
+    // Only the configured council may veto
+    let err = process(&mut context, &[veto_proposal_instruction(&program_id, &admin, &proposals[1])], &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+
+    // Once voting ends the creator can no longer back out, but the council can still step in
+    set_clock(&mut context, draft.end_time + 1).await;
+    let err = process(&mut context, &[cancel(&proposals[1])], &[&creator]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::VotingEnded);
+    process(&mut context, &[queue(&proposals[1])], &[]).await.unwrap();
+    process(&mut context, &[veto(&proposals[1])], &[&council]).await.unwrap();
+    let vetoed = proposal(&mut context, &proposals[1]).await;
+    assert_eq!(vetoed.state, ProposalState::Vetoed);
+    set_clock(&mut context, vetoed.eta).await;
+    let execute = execute_proposal_instruction(&program_id, &proposals[1], &[]);
+    let err = process(&mut context, &[execute], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ProposalVetoed);
+
+    // A proposal nobody voted on is defeated, leaving nothing to veto
+    let defeated = proposal(&mut context, &proposals[2]).await;
+    assert_eq!(defeated.state_at(vetoed.eta), ProposalState::Defeated);
+    let err = process(&mut context, &[veto(&proposals[2])], &[&council]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::VotingEnded);
+}
+
+#[tokio::test]
+async fn test_pre_funded_proposal_address_does_not_block_creation() {
+    let program_id = Pubkey::new_unique();
+    let tola_program = token::ID;
+    let mut context = program_test(program_id).start_with_context().await;
+    let admin = context.payer.pubkey();
+    let (tola_state, _) = create_tola(&mut context, &tola_program, &admin);
+    initialize_governance(&mut context, &program_id, &tola_state).await;
+    let stake_pool = tola_address(STAKE_POOL_SEED);
+
+    let creator = Keypair::new();
+    let creator_stake = create_full_stake(&mut context, &tola_program, &creator.pubkey(), 120_000).await;
+    fund_lamports(&mut context, &creator.pubkey()).await;
+
+    // Anyone can send lamports to the next proposal address ahead of time
+    let next = proposal_address(&program_id, config(&mut context, &program_id).await.proposal_count);
+    let deposit = Rent::default().minimum_balance(0);
+    process(&mut context, &[system_instruction::transfer(&admin, &next, deposit)], &[]).await.unwrap();
+
+    let created = create_proposal(&mut context, &program_id, &stake_pool, &creator, &creator_stake, 3_600).await;
+    assert_eq!(created, next);
+    let account = context.banks_client.get_account(next).await.unwrap().unwrap();
+    assert_eq!(account.owner, program_id);
+    assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+    assert_eq!(proposal(&mut context, &next).await.creator, creator.pubkey());
+    assert_eq!(config(&mut context, &program_id).await.proposal_count, 1);
+}
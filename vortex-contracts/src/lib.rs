@@ -2,6 +2,7 @@ pub mod token;
 pub mod marketplace;
 pub mod governance;
 pub mod events;
+pub mod utils;
 
 #[cfg(test)]
 mod tests;
@@ -20,6 +21,37 @@ pub enum VortexError {
     InsufficientFunds,
     InvalidState,
     Unauthorized,
+    AlreadyInitialized,
+    NothingToClaim,
+    StakeLocked,
+    MathOverflow,
+    TokenPaused,
+    SupplyCapExceeded,
+    InvalidProof,
+    AlreadyClaimed,
+    PriceMismatch,
+    MarketplacePaused,
+    RoyaltyTooHigh,
+    PaymentMintNotAccepted,
+    AuctionClosed,
+    AuctionInProgress,
+    BidTooLow,
+    OfferExpired,
+    ListingExpired,
+    AlreadyVoted,
+    QuorumNotReached,
+    ApprovalThresholdNotMet,
+    ProposalNotQueued,
+    TimelockActive,
+    ProposalExpired,
+    VotingNotStarted,
+    VotingInProgress,
+    VotingEnded,
+    ProposalAlreadyQueued,
+    ProposalAlreadyExecuted,
+    ProposalCancelled,
+    ProposalVetoed,
+    StakeLockTooShort,
 }
 
 impl From<VortexError> for solana_program::program_error::ProgramError {
@@ -31,21 +63,3 @@ impl From<VortexError> for solana_program::program_error::ProgramError {
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-
-    #[test]
-    fn test_error_conversion() {
-        let error = VortexError::InvalidInstruction;
-        let program_error: solana_program::program_error::ProgramError = error.into();
-        assert!(matches!(program_error, solana_program::program_error::ProgramError::Custom(_)));
-    }
-}
@@ -1,45 +1,403 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
     pubkey::Pubkey,
+    system_program,
+    sysvar::{clock::Clock, Sysvar},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    events::{emit, VortexEvent},
+    utils::{
+        close_program_account, close_token_account, create_pda_account, create_token_account_pda, resize_account,
+        transfer_lamports, transfer_tokens, upgrade_authority,
+    },
+    VortexError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Seed prefix of `ArtworkListing` PDAs, followed by the seller and NFT mint
+pub const LISTING_SEED: &[u8] = b"listing";
+/// Seed prefix of listing escrow token accounts, followed by the listing
+pub const ESCROW_SEED: &[u8] = b"escrow";
+/// Seed prefix of `RoyaltyConfig` PDAs, followed by the NFT mint
+pub const ROYALTY_SEED: &[u8] = b"royalty";
+/// Most creators that can share an NFT's royalties
+pub const MAX_CREATORS: usize = 5;
+/// Seed prefix of `Auction` PDAs, followed by the seller and NFT mint
+pub const AUCTION_SEED: &[u8] = b"auction";
+/// Seed prefix of auction NFT escrow accounts, followed by the auction
+pub const AUCTION_ESCROW_SEED: &[u8] = b"auction_escrow";
+/// Seed prefix of auction bid escrow accounts, followed by the auction
+pub const BID_ESCROW_SEED: &[u8] = b"bid_escrow";
+/// Seed prefix of `BidRecord` PDAs, followed by the auction and bidder
+pub const BID_RECORD_SEED: &[u8] = b"bid_record";
+/// A bid this close to the end pushes the end back to this far from the bid
+pub const ANTI_SNIPING_WINDOW: i64 = 10 * 60;
+/// How long after an auction ends its leader must wait before they may take the
+/// winning bid back from an auction nobody managed to settle
+pub const SETTLEMENT_GRACE_PERIOD: i64 = 7 * 24 * 60 * 60;
+/// Seed prefix of `Offer` PDAs, followed by the buyer and NFT mint
+pub const OFFER_SEED: &[u8] = b"offer";
+/// Seed prefix of offer escrow token accounts, followed by the offer
+pub const OFFER_ESCROW_SEED: &[u8] = b"offer_escrow";
+/// Seed prefix of `Collection` PDAs, followed by the collection key
+pub const COLLECTION_SEED: &[u8] = b"collection";
+/// Seed prefix of `CollectionMember` PDAs, followed by the collection and NFT mint
+pub const COLLECTION_MEMBER_SEED: &[u8] = b"collection_member";
+/// Seed of the `MarketplaceConfig` PDA
+pub const CONFIG_SEED: &[u8] = b"marketplace_config";
+/// Most payment mints the config may accept
+pub const MAX_ACCEPTED_MINTS: usize = 16;
+/// Payment mint standing for SOL paid in lamports rather than an SPL token
+pub const NATIVE_SOL_MINT: Pubkey = system_program::ID;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum MarketplaceInstruction {
-    /// List an artwork for sale
+    /// List an artwork for sale, moving the NFT into escrow
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The seller's account
-    /// 1. `[writable]` The listing account
+    /// 0. `[signer, writable]` The seller's account
+    /// 1. `[writable]` The listing account (PDA `["listing", seller, nft_mint]`)
     /// 2. `[]` The NFT mint account
     /// 3. `[writable]` The seller's NFT account
+    /// 4. `[writable]` The escrow NFT account (PDA `["escrow", listing]`)
+    /// 5. `[]` The token program
+    /// 6. `[]` The system program
+    /// 7. `[]` The royalty config account (PDA `["royalty", nft_mint]`), empty if never registered
+    /// 8. `[]` The seller's token account receiving proceeds, whose mint is the payment
+    ///    mint, or the seller's own account to be paid in SOL
+    /// 9. `[]` The marketplace config account
     ListArtwork {
         price: u64,
+        /// Must match the registered royalty, or be zero if none is registered
         royalty_percentage: u8,
     },
 
-    /// Purchase an artwork
+    /// Purchase an artwork, splitting payment between seller, creator and platform
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The buyer's account
+    /// For listings paid in SOL, every token account below is instead the
+    /// wallet it would belong to, and the buyer pays from their own account.
+    ///
+    /// 0. `[signer, writable]` The buyer's account
     /// 1. `[writable]` The listing account
     /// 2. `[writable]` The seller's token account
     /// 3. `[writable]` The buyer's token account
-    /// 4. `[writable]` The NFT account
+    /// 4. `[writable]` The escrow NFT account
+    /// 5. `[writable]` The buyer's NFT account
+    /// 6. `[]` The royalty config account
+    /// 7. `[writable]` The platform fee token account, owned by the config's fee recipient
+    /// 8. `[]` The token program
+    /// 9. `[]` The marketplace config account
+    /// 10. `[]` The system program
+    /// 11. `[writable]` One token account per registered creator, in registration order, from here on
     PurchaseArtwork {
+        /// Must equal a fixed listing price, guarding against a price change in
+        /// flight; for a Dutch auction it caps the current price that is charged
         price: u64,
     },
 
-    /// Cancel a listing
+    /// Cancel a listing and return the NFT to the seller
     /// 
     /// Accounts expected:
     /// 0. `[signer]` The seller's account
     /// 1. `[writable]` The listing account
+    /// 2. `[writable]` The escrow NFT account
+    /// 3. `[writable]` The seller's NFT account
+    /// 4. `[]` The token program
     CancelListing {},
+
+    /// Create the marketplace config; only the program's upgrade authority
+    /// may, and it becomes the admin
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The upgrade authority
+    /// 1. `[writable]` The config account (PDA `["marketplace_config"]`)
+    /// 2. `[]` The system program
+    /// 3. `[]` The program's ProgramData account
+    InitializeConfig {
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        max_royalty_percentage: u8,
+        accepted_mints: Vec<Pubkey>,
+    },
+
+    /// Replace the marketplace config
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The current admin
+    /// 1. `[writable]` The config account
+    /// 2. `[]` The system program
+    UpdateConfig {
+        admin: Pubkey,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        max_royalty_percentage: u8,
+        accepted_mints: Vec<Pubkey>,
+        paused: bool,
+    },
+
+    /// Register how an NFT's royalties are split between its creators
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The minter, who must still be the mint authority
+    /// 1. `[writable]` The royalty config account (PDA `["royalty", nft_mint]`)
+    /// 2. `[]` The NFT mint account
+    /// 3. `[]` The system program
+    RegisterRoyalties {
+        royalty_percentage: u8,
+        /// Shares must add up to 10000 basis points
+        creators: Vec<CreatorShare>,
+    },
+
+    /// Put an NFT up for English auction, moving it into escrow
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The seller's account
+    /// 1. `[writable]` The auction account (PDA `["auction", seller, nft_mint]`)
+    /// 2. `[]` The NFT mint account
+    /// 3. `[writable]` The seller's NFT account
+    /// 4. `[writable]` The NFT escrow account (PDA `["auction_escrow", auction]`)
+    /// 5. `[]` The payment mint account
+    /// 6. `[writable]` The bid escrow account (PDA `["bid_escrow", auction]`)
+    /// 7. `[]` The seller's token account receiving proceeds
+    /// 8. `[]` The royalty config account
+    /// 9. `[]` The marketplace config account
+    /// 10. `[]` The token program
+    /// 11. `[]` The system program
+    CreateAuction {
+        reserve: u64,
+        start: i64,
+        end: i64,
+        /// Each bid must beat the last by at least this much
+        min_increment_bps: u16,
+    },
+
+    /// Bid on an auction. The previous high bid stays in escrow, credited to
+    /// its bidder's record until they withdraw it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The bidder, paying for their bid record
+    /// 1. `[writable]` The auction account
+    /// 2. `[writable]` The bidder's token account
+    /// 3. `[writable]` The bid escrow account
+    /// 4. `[writable]` The bidder's bid record (PDA `["bid_record", auction, bidder]`)
+    /// 5. `[writable]` The previous high bidder's bid record (any account if there are no bids)
+    /// 6. `[]` The marketplace config account
+    /// 7. `[]` The token program
+    /// 8. `[]` The system program
+    PlaceBid {
+        amount: u64,
+    },
+
+    /// Close an ended auction: the winner gets the NFT and the bid is paid
+    /// out, or the NFT goes back to the seller if nobody bid
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The auction account
+    /// 1. `[writable]` The NFT escrow account
+    /// 2. `[writable]` The bid escrow account
+    /// 3. `[writable]` The winner's NFT account, or the seller's if there are no bids
+    /// 4. `[writable]` The seller's token account
+    /// 5. `[]` The royalty config account
+    /// 6. `[writable]` The platform fee token account
+    /// 7. `[]` The marketplace config account
+    /// 8. `[]` The token program
+    /// 9. `[writable]` One token account per registered creator, in registration order, from here on
+    SettleAuction {},
+
+    /// Withdraw an auction nobody has bid on
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The seller's account
+    /// 1. `[writable]` The auction account
+    /// 2. `[writable]` The NFT escrow account
+    /// 3. `[writable]` The seller's NFT account
+    /// 4. `[]` The token program
+    CancelAuction {},
+
+    /// List an artwork as a Dutch auction whose price falls from
+    /// `terms.start_price` to `terms.floor_price` over the auction window
+    ///
+    /// Accounts expected: as for `ListArtwork`
+    ListDutchAuction {
+        terms: DutchAuctionTerms,
+        /// Must match the registered royalty, or be zero if none is registered
+        royalty_percentage: u8,
+    },
+
+    /// Offer to buy an NFT whether or not it is listed, escrowing the payment
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The buyer
+    /// 1. `[writable]` The offer account (PDA `["offer", buyer, nft_mint]`)
+    /// 2. `[writable]` The buyer's token account, also used for the refund
+    /// 3. `[]` The payment mint account
+    /// 4. `[writable]` The offer escrow account (PDA `["offer_escrow", offer]`)
+    /// 5. `[]` The marketplace config account
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    MakeOffer {
+        nft_mint: Pubkey,
+        amount: u64,
+        expiry: i64,
+    },
+
+    /// Sell the NFT to the offer's buyer
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current holder of the NFT
+    /// 1. `[writable]` The offer account
+    /// 2. `[writable]` The offer escrow account
+    /// 3. `[writable]` The holder's NFT account
+    /// 4. `[writable]` The buyer's NFT account
+    /// 5. `[writable]` The holder's token account receiving proceeds
+    /// 6. `[]` The royalty config account
+    /// 7. `[writable]` The platform fee token account
+    /// 8. `[]` The marketplace config account
+    /// 9. `[]` The token program
+    /// 10. `[writable]` One token account per registered creator, in registration order, from here on
+    AcceptOffer {},
+
+    /// Withdraw an offer and refund the escrowed payment
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The buyer
+    /// 1. `[writable]` The offer account
+    /// 2. `[writable]` The offer escrow account
+    /// 3. `[writable]` The buyer's refund token account
+    /// 4. `[]` The token program
+    CancelOffer {},
+
+    /// Register a collection, making the signer its authority
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The collection authority
+    /// 1. `[signer]` The collection key
+    /// 2. `[writable]` The collection account (PDA `["collection", collection_key]`)
+    /// 3. `[]` The system program
+    CreateCollection {},
+
+    /// Record an NFT as a verified member of a collection. The NFT's mint
+    /// authority must already be revoked, so its supply can never grow past
+    /// one; register royalties before revoking it.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The collection authority
+    /// 1. `[]` The collection account
+    /// 2. `[]` The NFT mint account
+    /// 3. `[writable]` The member account (PDA `["collection_member", collection, nft_mint]`)
+    /// 4. `[]` The system program
+    VerifyCollectionMember {},
+
+    /// Offer to buy any one verified member of a collection
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The buyer
+    /// 1. `[writable]` The offer account (PDA `["offer", buyer, collection]`)
+    /// 2. `[writable]` The buyer's token account, also used for the refund
+    /// 3. `[]` The payment mint account
+    /// 4. `[writable]` The offer escrow account (PDA `["offer_escrow", offer]`)
+    /// 5. `[]` The marketplace config account
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    /// 8. `[]` The collection account
+    MakeCollectionOffer {
+        collection: Pubkey,
+        amount: u64,
+        expiry: i64,
+    },
+
+    /// Sell a member of the collection to a collection offer's buyer
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The holder of a member NFT
+    /// 1. `[writable]` The offer account
+    /// 2. `[writable]` The offer escrow account
+    /// 3. `[writable]` The holder's NFT account
+    /// 4. `[writable]` The buyer's NFT account
+    /// 5. `[writable]` The holder's token account receiving proceeds
+    /// 6. `[]` The member account of the NFT being sold
+    /// 7. `[]` The royalty config account
+    /// 8. `[writable]` The platform fee token account
+    /// 9. `[]` The marketplace config account
+    /// 10. `[]` The token program
+    /// 11. `[writable]` One token account per registered creator, in registration order, from here on
+    AcceptCollectionOffer {},
+
+    /// Buy the cheapest of the given listings in a collection
+    ///
+    /// Listings are bought in ascending price order until `max_count` have
+    /// been bought or the next one would take the total over `max_total`.
+    /// Listings that are no longer active or purchasable are skipped.
+    ///
+    /// Every listing must be paid in the same currency; for SOL the token
+    /// accounts are the wallets they would belong to, as in `PurchaseArtwork`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The buyer
+    /// 1. `[writable]` The buyer's token account
+    /// 2. `[]` The collection account
+    /// 3. `[writable]` The platform fee token account
+    /// 4. `[]` The marketplace config account
+    /// 5. `[]` The token program
+    /// 6. `[]` The system program
+    /// 7. Per candidate listing, from here on:
+    ///    `[writable]` the listing account,
+    ///    `[writable]` its escrow account,
+    ///    `[writable]` the seller's token account,
+    ///    `[writable]` the buyer's NFT account,
+    ///    `[]` the member account of the listed NFT,
+    ///    `[]` the royalty config account,
+    ///    then `[writable]` one token account per registered creator
+    SweepFloor {
+        max_count: u8,
+        max_total: u64,
+    },
+
+    /// Reprice a fixed-price listing and set when it expires
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The seller
+    /// 1. `[writable]` The listing account
+    UpdateListing {
+        new_price: u64,
+        /// Unix timestamp the listing stops being purchasable, or 0 for never
+        new_expiry: i64,
+    },
+
+    /// Return an expired listing's NFT and rent to the seller; callable by anyone
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The listing account
+    /// 1. `[writable]` The escrow account
+    /// 2. `[writable]` The seller's NFT account
+    /// 3. `[writable]` The seller
+    /// 4. `[]` The token program
+    CloseExpiredListing {},
+
+    /// Withdraw the outbid amounts credited to a bid record, closing the
+    /// record unless the bidder currently leads the auction. Once an auction
+    /// has gone unsettled for `SETTLEMENT_GRACE_PERIOD` past its end, as when
+    /// a payout account is closed or frozen, the leader may take back the
+    /// winning bid too, leaving the auction without bids so the NFT can go
+    /// back to the seller
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The bidder, who gets the record's rent back
+    /// 1. `[writable]` The auction account
+    /// 2. `[writable]` The bidder's bid record
+    /// 3. `[writable]` The bid escrow account
+    /// 4. `[writable]` The token account to refund into
+    /// 5. `[]` The token program
+    WithdrawBidRefund {},
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
@@ -49,10 +407,292 @@ pub struct ArtworkListing {
     pub price: u64,
     pub royalty_percentage: u8,
     pub is_active: bool,
+    /// Token account holding the NFT while listed
+    pub escrow: Pubkey,
+    pub bump: u8,
+    /// Seller's token account proceeds are paid into
+    pub payment_account: Pubkey,
+    pub kind: ListingKind,
+    /// Only meaningful for `ListingKind::DutchAuction`
+    pub dutch_auction: DutchAuctionTerms,
+    /// Unix timestamp the listing stops being purchasable, or 0 for never
+    pub expires_at: i64,
+    /// Mint the price is paid in, or `NATIVE_SOL_MINT` for SOL
+    pub payment_mint: Pubkey,
+}
+
+impl ArtworkListing {
+    pub const LEN: usize = 32 + 32 + 8 + 1 + 1 + 32 + 1 + 32 + 1 + DutchAuctionTerms::LEN + 8 + 32;
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    /// Price a purchase at `now` would pay
+    pub fn current_price(&self, now: i64) -> Result<u64, ProgramError> {
+        match self.kind {
+            ListingKind::FixedPrice => Ok(self.price),
+            ListingKind::DutchAuction => self.dutch_auction.price_at(now),
+        }
+    }
+
+    pub fn signer_seeds<'a>(&'a self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [LISTING_SEED, self.seller.as_ref(), self.nft_mint.as_ref(), bump]
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum ListingKind {
+    #[default]
+    FixedPrice,
+    DutchAuction,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct DutchAuctionTerms {
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Zero decays linearly; otherwise the price drops once every `step_duration` seconds
+    pub step_duration: i64,
+}
+
+impl DutchAuctionTerms {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8;
+
+    pub fn validate(&self) -> ProgramResult {
+        if self.floor_price == 0 || self.start_price <= self.floor_price || self.start_time >= self.end_time {
+            return Err(VortexError::InvalidInstruction.into());
+        }
+        let duration = self.end_time.checked_sub(self.start_time).ok_or(VortexError::MathOverflow)?;
+        if self.step_duration < 0 || self.step_duration > duration {
+            return Err(VortexError::InvalidInstruction.into());
+        }
+        Ok(())
+    }
+
+    /// Start price until `start_time`, floor price from `end_time`, and in
+    /// between the linear decay, held flat within each step when stepwise
+    pub fn price_at(&self, now: i64) -> Result<u64, ProgramError> {
+        if now <= self.start_time {
+            return Ok(self.start_price);
+        }
+        if now >= self.end_time {
+            return Ok(self.floor_price);
+        }
+        let mut elapsed = now.checked_sub(self.start_time).ok_or(VortexError::MathOverflow)?;
+        if self.step_duration > 0 {
+            elapsed -= elapsed % self.step_duration;
+        }
+        let duration = self.end_time.checked_sub(self.start_time).ok_or(VortexError::MathOverflow)?;
+        let range = self.start_price.checked_sub(self.floor_price).ok_or(VortexError::MathOverflow)?;
+        let drop = range as u128 * elapsed as u128 / duration as u128;
+        Ok(self.start_price - drop as u64)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Auction {
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    /// Seller's token account proceeds are paid into
+    pub payment_account: Pubkey,
+    pub reserve: u64,
+    pub start: i64,
+    /// Pushed back by late bids
+    pub end: i64,
+    pub min_increment_bps: u16,
+    pub royalty_percentage: u8,
+    pub highest_bid: u64,
+    pub highest_bidder: Pubkey,
+    pub is_active: bool,
+    pub nft_escrow: Pubkey,
+    pub bid_escrow: Pubkey,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 2 + 1 + 8 + 32 + 1 + 32 + 32 + 1;
+
+    /// Smallest bid that would be accepted now
+    pub fn min_bid(&self) -> u64 {
+        if self.highest_bid == 0 {
+            return self.reserve;
+        }
+        let increment = (self.highest_bid as u128 * self.min_increment_bps as u128 / 10_000) as u64;
+        self.highest_bid.saturating_add(increment.max(1))
+    }
+
+    pub fn signer_seeds<'a>(&'a self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [AUCTION_SEED, self.seller.as_ref(), self.nft_mint.as_ref(), bump]
+    }
+}
+
+/// What an auction owes one bidder for bids that were outbid
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct BidRecord {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    /// Outbid amounts held in the bid escrow for this bidder
+    pub refundable: u64,
+    pub bump: u8,
+}
+
+impl BidRecord {
+    pub const LEN: usize = 32 + 32 + 8 + 1;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Offer {
+    pub buyer: Pubkey,
+    /// The NFT mint, or the collection account for collection offers
+    pub nft_mint: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+    /// Buyer's token account the payment came from and is refunded to
+    pub refund_account: Pubkey,
+    pub is_active: bool,
+    pub escrow: Pubkey,
+    pub bump: u8,
+    pub is_collection: bool,
+}
+
+impl Offer {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 32 + 1 + 32 + 1 + 1;
+
+    pub fn signer_seeds<'a>(&'a self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [OFFER_SEED, self.buyer.as_ref(), self.nft_mint.as_ref(), bump]
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Collection {
+    pub authority: Pubkey,
+    pub collection_key: Pubkey,
+    pub num_members: u32,
+    pub bump: u8,
+}
+
+impl Collection {
+    pub const LEN: usize = 32 + 32 + 4 + 1;
+}
+
+/// Proof that `nft_mint` was verified into `collection` by its authority
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CollectionMember {
+    pub collection: Pubkey,
+    pub nft_mint: Pubkey,
+    pub bump: u8,
+}
+
+impl CollectionMember {
+    pub const LEN: usize = 32 + 32 + 1;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CreatorShare {
+    pub address: Pubkey,
+    pub share_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RoyaltyConfig {
+    pub nft_mint: Pubkey,
+    pub royalty_percentage: u8,
+    pub bump: u8,
+    pub creators: Vec<CreatorShare>,
+}
+
+impl RoyaltyConfig {
+    pub fn len(num_creators: usize) -> usize {
+        32 + 1 + 1 + 4 + num_creators * (32 + 2)
+    }
+
+    pub fn validate(&self) -> ProgramResult {
+        let mut total: u64 = 0;
+        for (i, creator) in self.creators.iter().enumerate() {
+            if creator.share_bps == 0 || self.creators[..i].iter().any(|c| c.address == creator.address) {
+                return Err(VortexError::InvalidInstruction.into());
+            }
+            total += creator.share_bps as u64;
+        }
+        if self.creators.is_empty()
+            || self.creators.len() > MAX_CREATORS
+            || total != 10_000
+            || self.royalty_percentage > 100
+        {
+            return Err(VortexError::InvalidInstruction.into());
+        }
+        Ok(())
+    }
+
+    /// Split `royalty` by share; the last creator also takes the rounding dust
+    pub fn split(&self, royalty: u64) -> Vec<u64> {
+        let mut remaining = royalty;
+        let mut amounts: Vec<u64> = self.creators[..self.creators.len() - 1]
+            .iter()
+            .map(|creator| {
+                let amount = (royalty as u128 * creator.share_bps as u128 / 10_000) as u64;
+                remaining -= amount;
+                amount
+            })
+            .collect();
+        amounts.push(remaining);
+        amounts
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MarketplaceConfig {
+    pub admin: Pubkey,
+    pub fee_bps: u16,
+    /// Wallet whose token accounts receive platform fees
+    pub fee_recipient: Pubkey,
+    pub max_royalty_percentage: u8,
+    pub paused: bool,
+    pub bump: u8,
+    pub accepted_mints: Vec<Pubkey>,
+}
+
+impl MarketplaceConfig {
+    pub fn len(num_mints: usize) -> usize {
+        32 + 2 + 32 + 1 + 1 + 1 + 4 + num_mints * 32
+    }
+
+    /// Fee and the largest royalty together may not exceed the price
+    pub fn validate(&self) -> ProgramResult {
+        if self.max_royalty_percentage > 100
+            || self.fee_bps as u64 + self.max_royalty_percentage as u64 * 100 > 10_000
+            || self.accepted_mints.len() > MAX_ACCEPTED_MINTS
+        {
+            return Err(VortexError::InvalidInstruction.into());
+        }
+        Ok(())
+    }
+
+    pub fn assert_not_paused(&self) -> ProgramResult {
+        if self.paused {
+            return Err(VortexError::MarketplacePaused.into());
+        }
+        Ok(())
+    }
+}
+
+/// Split `price` into (royalty, platform fee, seller proceeds)
+pub fn split_payment(price: u64, royalty_percentage: u8, fee_bps: u16) -> Result<(u64, u64, u64), ProgramError> {
+    let royalty = (price as u128 * royalty_percentage as u128 / 100) as u64;
+    let fee = (price as u128 * fee_bps as u128 / 10_000) as u64;
+    let proceeds = price
+        .checked_sub(royalty)
+        .and_then(|p| p.checked_sub(fee))
+        .ok_or(VortexError::MathOverflow)?;
+    Ok((royalty, fee, proceeds))
 }
 
 // Entry point
-entrypoint!(process_instruction);
+#[cfg(all(feature = "marketplace-program", not(feature = "no-entrypoint")))]
+solana_program::entrypoint!(process_instruction);
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -64,7 +704,14 @@ pub fn process_instruction(
     match instruction {
         MarketplaceInstruction::ListArtwork { price, royalty_percentage } => {
             msg!("Instruction: List Artwork");
-            process_list_artwork(program_id, accounts, price, royalty_percentage)
+            process_list_artwork(
+                program_id,
+                accounts,
+                price,
+                royalty_percentage,
+                ListingKind::FixedPrice,
+                DutchAuctionTerms::default(),
+            )
         }
         MarketplaceInstruction::PurchaseArtwork { price } => {
             msg!("Instruction: Purchase Artwork");
@@ -74,25 +721,222 @@ pub fn process_instruction(
             msg!("Instruction: Cancel Listing");
             process_cancel_listing(program_id, accounts)
         }
+        MarketplaceInstruction::InitializeConfig { fee_bps, fee_recipient, max_royalty_percentage, accepted_mints } => {
+            msg!("Instruction: Initialize Config");
+            let config = MarketplaceConfig {
+                admin: Pubkey::default(),
+                fee_bps,
+                fee_recipient,
+                max_royalty_percentage,
+                paused: false,
+                bump: 0,
+                accepted_mints,
+            };
+            process_initialize_config(program_id, accounts, config)
+        }
+        MarketplaceInstruction::UpdateConfig {
+            admin,
+            fee_bps,
+            fee_recipient,
+            max_royalty_percentage,
+            accepted_mints,
+            paused,
+        } => {
+            msg!("Instruction: Update Config");
+            let config = MarketplaceConfig {
+                admin,
+                fee_bps,
+                fee_recipient,
+                max_royalty_percentage,
+                paused,
+                bump: 0,
+                accepted_mints,
+            };
+            process_update_config(program_id, accounts, config)
+        }
+        MarketplaceInstruction::RegisterRoyalties { royalty_percentage, creators } => {
+            msg!("Instruction: Register Royalties");
+            process_register_royalties(program_id, accounts, royalty_percentage, creators)
+        }
+        MarketplaceInstruction::CreateAuction { reserve, start, end, min_increment_bps } => {
+            msg!("Instruction: Create Auction");
+            process_create_auction(program_id, accounts, reserve, start, end, min_increment_bps)
+        }
+        MarketplaceInstruction::PlaceBid { amount } => {
+            msg!("Instruction: Place Bid");
+            process_place_bid(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::SettleAuction {} => {
+            msg!("Instruction: Settle Auction");
+            process_settle_auction(program_id, accounts)
+        }
+        MarketplaceInstruction::CancelAuction {} => {
+            msg!("Instruction: Cancel Auction");
+            process_cancel_auction(program_id, accounts)
+        }
+        MarketplaceInstruction::WithdrawBidRefund {} => {
+            msg!("Instruction: Withdraw Bid Refund");
+            process_withdraw_bid_refund(program_id, accounts)
+        }
+        MarketplaceInstruction::ListDutchAuction { terms, royalty_percentage } => {
+            msg!("Instruction: List Dutch Auction");
+            terms.validate()?;
+            process_list_artwork(
+                program_id,
+                accounts,
+                terms.start_price,
+                royalty_percentage,
+                ListingKind::DutchAuction,
+                terms,
+            )
+        }
+        MarketplaceInstruction::MakeOffer { nft_mint, amount, expiry } => {
+            msg!("Instruction: Make Offer");
+            process_make_offer(program_id, accounts, nft_mint, amount, expiry, false)
+        }
+        MarketplaceInstruction::AcceptOffer {} => {
+            msg!("Instruction: Accept Offer");
+            process_accept_offer(program_id, accounts, false)
+        }
+        MarketplaceInstruction::CancelOffer {} => {
+            msg!("Instruction: Cancel Offer");
+            process_cancel_offer(program_id, accounts)
+        }
+        MarketplaceInstruction::CreateCollection {} => {
+            msg!("Instruction: Create Collection");
+            process_create_collection(program_id, accounts)
+        }
+        MarketplaceInstruction::VerifyCollectionMember {} => {
+            msg!("Instruction: Verify Collection Member");
+            process_verify_collection_member(program_id, accounts)
+        }
+        MarketplaceInstruction::MakeCollectionOffer { collection, amount, expiry } => {
+            msg!("Instruction: Make Collection Offer");
+            process_make_offer(program_id, accounts, collection, amount, expiry, true)
+        }
+        MarketplaceInstruction::AcceptCollectionOffer {} => {
+            msg!("Instruction: Accept Collection Offer");
+            process_accept_offer(program_id, accounts, true)
+        }
+        MarketplaceInstruction::SweepFloor { max_count, max_total } => {
+            msg!("Instruction: Sweep Floor");
+            process_sweep_floor(program_id, accounts, max_count, max_total)
+        }
+        MarketplaceInstruction::UpdateListing { new_price, new_expiry } => {
+            msg!("Instruction: Update Listing");
+            process_update_listing(program_id, accounts, new_price, new_expiry)
+        }
+        MarketplaceInstruction::CloseExpiredListing {} => {
+            msg!("Instruction: Close Expired Listing");
+            process_close_expired_listing(program_id, accounts)
+        }
     }
 }
 
+// Escrow the NFT and open (or reopen) the listing
 fn process_list_artwork(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     price: u64,
     royalty_percentage: u8,
+    kind: ListingKind,
+    dutch_auction: DutchAuctionTerms,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let seller = next_account_info(account_info_iter)?;
     let listing_account = next_account_info(account_info_iter)?;
     let nft_mint = next_account_info(account_info_iter)?;
     let seller_nft_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let royalty_account = next_account_info(account_info_iter)?;
+    let payment_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
 
     // Verify seller is signer
     if !seller.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if price == 0 {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let config = load_config(program_id, config_account)?;
+    config.assert_not_paused()?;
+    if royalty_percentage > config.max_royalty_percentage {
+        return Err(VortexError::RoyaltyTooHigh.into());
+    }
+
+    // Only single-edition mints can be listed
+    if nft_mint.owner != token_program.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mint = spl_token::state::Mint::unpack(&nft_mint.data.borrow())?;
+    if mint.decimals != 0 || mint.supply != 1 {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let registered = load_royalty_config(program_id, royalty_account, nft_mint.key)?
+        .map_or(0, |royalties| royalties.royalty_percentage);
+    if royalty_percentage != registered {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+    // Passing the seller's own account asks to be paid in SOL
+    let payment_mint = if payment_account.key == seller.key {
+        NATIVE_SOL_MINT
+    } else {
+        if payment_account.owner != token_program.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        let payment = spl_token::state::Account::unpack(&payment_account.data.borrow())?;
+        if payment.owner != *seller.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        payment.mint
+    };
+    if !config.accepted_mints.contains(&payment_mint) {
+        return Err(VortexError::PaymentMintNotAccepted.into());
+    }
+
+    let (listing_key, listing_bump) = Pubkey::find_program_address(
+        &[LISTING_SEED, seller.key.as_ref(), nft_mint.key.as_ref()],
+        program_id,
+    );
+    let (escrow_key, escrow_bump) =
+        Pubkey::find_program_address(&[ESCROW_SEED, listing_key.as_ref()], program_id);
+    if *listing_account.key != listing_key || *escrow_account.key != escrow_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // A listing PDA outlives its sale, so relisting reuses it
+    if listing_account.data_is_empty() {
+        create_pda_account(
+            seller,
+            listing_account,
+            system_program,
+            program_id,
+            ArtworkListing::LEN,
+            &[LISTING_SEED, seller.key.as_ref(), nft_mint.key.as_ref(), &[listing_bump]],
+        )?;
+    } else if ArtworkListing::try_from_slice(&listing_account.data.borrow())?.is_active {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+    if escrow_account.data_is_empty() {
+        create_token_account_pda(
+            seller,
+            escrow_account,
+            nft_mint,
+            &listing_key,
+            system_program,
+            token_program,
+            &[ESCROW_SEED, listing_key.as_ref(), &[escrow_bump]],
+        )?;
+    }
+
+    transfer_tokens(token_program, seller_nft_account, escrow_account, seller, 1, &[])?;
 
     // Create listing
     let listing = ArtworkListing {
@@ -101,9 +945,23 @@ fn process_list_artwork(
         price,
         royalty_percentage,
         is_active: true,
+        escrow: escrow_key,
+        bump: listing_bump,
+        payment_account: *payment_account.key,
+        kind,
+        dutch_auction,
+        expires_at: 0,
+        payment_mint,
     };
+    listing.serialize(&mut &mut listing_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ArtworkListed {
+        seller: *seller.key,
+        nft_mint: *nft_mint.key,
+        price,
+        royalty_percentage,
+    });
 
-    listing.serialize(&mut *listing_account.data.borrow_mut())?;
     Ok(())
 }
 
@@ -117,19 +975,199 @@ fn process_purchase_artwork(
     let listing_account = next_account_info(account_info_iter)?;
     let seller_token_account = next_account_info(account_info_iter)?;
     let buyer_token_account = next_account_info(account_info_iter)?;
-    let nft_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let buyer_nft_account = next_account_info(account_info_iter)?;
+    let royalty_account = next_account_info(account_info_iter)?;
+    let fee_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let creator_accounts = account_info_iter.as_slice();
 
     // Verify buyer is signer
     if !buyer.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = load_config(program_id, config_account)?;
+    config.assert_not_paused()?;
+
+    let listing = load_listing(program_id, listing_account)?;
+    if !listing.is_active {
+        return Err(VortexError::InvalidState.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if listing.is_expired(now) {
+        return Err(VortexError::ListingExpired.into());
+    }
+    let price = match listing.kind {
+        ListingKind::FixedPrice if price != listing.price => return Err(VortexError::PriceMismatch.into()),
+        ListingKind::FixedPrice => price,
+        ListingKind::DutchAuction if now < listing.dutch_auction.start_time => {
+            return Err(VortexError::AuctionClosed.into())
+        }
+        ListingKind::DutchAuction => {
+            let current = listing.current_price(now)?;
+            if current > price {
+                return Err(VortexError::PriceMismatch.into());
+            }
+            current
+        }
+    };
+
+    // SOL is paid straight out of the buyer's own account
+    let native = listing.payment_mint == NATIVE_SOL_MINT;
+    if native && buyer_token_account.key != buyer.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let sale = SaleAccounts {
+        token_program,
+        system_program: native.then_some(system_program),
+        source: buyer_token_account,
+        authority: buyer,
+        seller_token_account,
+        royalty_account,
+        fee_account,
+        creator_accounts,
+    };
+    complete_purchase(
+        program_id,
+        &config,
+        &sale,
+        listing_account,
+        listing,
+        escrow_account,
+        buyer_nft_account,
+        price,
+    )
+}
+
+// Pay for a listing out of `sale.source` and release the NFT to the buyer
+#[allow(clippy::too_many_arguments)]
+fn complete_purchase<'a>(
+    program_id: &Pubkey,
+    config: &MarketplaceConfig,
+    sale: &SaleAccounts<'a, '_>,
+    listing_account: &AccountInfo<'a>,
+    mut listing: ArtworkListing,
+    escrow_account: &AccountInfo<'a>,
+    buyer_nft_account: &AccountInfo<'a>,
+    price: u64,
+) -> ProgramResult {
+    if listing.escrow != *escrow_account.key || listing.payment_account != *sale.seller_token_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if (listing.payment_mint == NATIVE_SOL_MINT) != sale.system_program.is_some() {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    // The allowlist may have changed since the listing was made
+    if !config.accepted_mints.contains(&listing.payment_mint) {
+        return Err(VortexError::PaymentMintNotAccepted.into());
+    }
+
+    settle_sale(program_id, config, sale, &listing.nft_mint, price, listing.royalty_percentage, &[])?;
+
+    let bump = [listing.bump];
+    transfer_tokens(
+        sale.token_program,
+        escrow_account,
+        buyer_nft_account,
+        listing_account,
+        1,
+        &[&listing.signer_seeds(&bump)],
+    )?;
+
+    listing.is_active = false;
+    listing.serialize(&mut &mut listing_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ArtworkPurchased {
+        buyer: *sale.authority.key,
+        seller: listing.seller,
+        nft_mint: listing.nft_mint,
+        price,
+    });
+
+    Ok(())
+}
 
-    // Transfer tokens and NFT
-    // Implementation details to be added
+// Accounts a sale pays out through
+struct SaleAccounts<'a, 'b> {
+    token_program: &'b AccountInfo<'a>,
+    /// Set when the price is paid in lamports, making every payee a wallet
+    system_program: Option<&'b AccountInfo<'a>>,
+    /// Token account the price is paid from
+    source: &'b AccountInfo<'a>,
+    authority: &'b AccountInfo<'a>,
+    seller_token_account: &'b AccountInfo<'a>,
+    royalty_account: &'b AccountInfo<'a>,
+    fee_account: &'b AccountInfo<'a>,
+    /// One token account per registered creator
+    creator_accounts: &'b [AccountInfo<'a>],
+}
+
+// Pay `price` out of the sale source to the creators, the platform and the seller
+fn settle_sale(
+    program_id: &Pubkey,
+    config: &MarketplaceConfig,
+    sale: &SaleAccounts,
+    nft_mint: &Pubkey,
+    price: u64,
+    royalty_percentage: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    // Royalty and fee destinations must belong to the creators and the platform
+    let payee = |account: &AccountInfo| -> Result<Pubkey, ProgramError> {
+        if sale.system_program.is_some() {
+            return Ok(*account.key);
+        }
+        if account.owner != sale.token_program.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        Ok(spl_token::state::Account::unpack(&account.data.borrow())?.owner)
+    };
+    if payee(sale.fee_account)? != config.fee_recipient {
+        return Err(VortexError::InvalidAccount.into());
+    }
 
+    let (royalty, fee, proceeds) = split_payment(price, royalty_percentage, config.fee_bps)?;
+    let mut payouts = vec![(sale.fee_account, fee), (sale.seller_token_account, proceeds)];
+    if royalty > 0 {
+        let royalties = load_royalty_config(program_id, sale.royalty_account, nft_mint)?
+            .ok_or(VortexError::InvalidAccount)?;
+        if sale.creator_accounts.len() < royalties.creators.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        for ((creator, account), amount) in royalties
+            .creators
+            .iter()
+            .zip(sale.creator_accounts)
+            .zip(royalties.split(royalty))
+        {
+            if payee(account)? != creator.address {
+                return Err(VortexError::InvalidAccount.into());
+            }
+            payouts.push((account, amount));
+        }
+    }
+    for (destination, amount) in payouts {
+        if amount == 0 {
+            continue;
+        }
+        match sale.system_program {
+            Some(system_program) => transfer_lamports(sale.source, destination, system_program, amount)?,
+            None => {
+                transfer_tokens(sale.token_program, sale.source, destination, sale.authority, amount, signer_seeds)?
+            }
+        }
+    }
     Ok(())
 }
 
+// Close the sale and hand the escrowed NFT back
 fn process_cancel_listing(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -137,19 +1175,1353 @@ fn process_cancel_listing(
     let account_info_iter = &mut accounts.iter();
     let seller = next_account_info(account_info_iter)?;
     let listing_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let seller_nft_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
 
     // Verify seller is signer
     if !seller.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Deactivate listing
-    let mut listing = ArtworkListing::try_from_slice(&listing_account.data.borrow())?;
+    let mut listing = load_listing(program_id, listing_account)?;
     if listing.seller != *seller.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(VortexError::Unauthorized.into());
+    }
+    if !listing.is_active {
+        return Err(VortexError::InvalidState.into());
+    }
+    if listing.escrow != *escrow_account.key {
+        return Err(VortexError::InvalidAccount.into());
     }
+
+    let bump = [listing.bump];
+    transfer_tokens(
+        token_program,
+        escrow_account,
+        seller_nft_account,
+        listing_account,
+        1,
+        &[&listing.signer_seeds(&bump)],
+    )?;
+
+    // Deactivate listing
     listing.is_active = false;
-    listing.serialize(&mut *listing_account.data.borrow_mut())?;
+    listing.serialize(&mut &mut listing_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ListingCancelled {
+        seller: listing.seller,
+        nft_mint: listing.nft_mint,
+    });
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Reprice a listing in place, keeping its escrow and rent
+fn process_update_listing(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_price: u64,
+    new_expiry: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller = next_account_info(account_info_iter)?;
+    let listing_account = next_account_info(account_info_iter)?;
+
+    if !seller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut listing = load_listing(program_id, listing_account)?;
+    if listing.seller != *seller.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    // Dutch auction prices follow their curve and cannot be edited
+    if !listing.is_active || listing.kind != ListingKind::FixedPrice {
+        return Err(VortexError::InvalidState.into());
+    }
+    if new_price == 0 || (new_expiry != 0 && new_expiry <= Clock::get()?.unix_timestamp) {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    listing.price = new_price;
+    listing.expires_at = new_expiry;
+    listing.serialize(&mut &mut listing_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::ListingUpdated {
+        seller: listing.seller,
+        nft_mint: listing.nft_mint,
+        price: new_price,
+        expires_at: new_expiry,
+    });
+
+    Ok(())
+}
+
+// Unwind an expired listing, returning the NFT and both accounts' rent
+fn process_close_expired_listing(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let listing_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let seller_nft_account = next_account_info(account_info_iter)?;
+    let seller = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let listing = load_listing(program_id, listing_account)?;
+    if !listing.is_active || !listing.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(VortexError::InvalidState.into());
+    }
+    if listing.seller != *seller.key || listing.escrow != *escrow_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Anyone can close, so the NFT must go back to an account the seller owns
+    if seller_nft_account.owner != token_program.key
+        || spl_token::state::Account::unpack(&seller_nft_account.data.borrow())?.owner != listing.seller
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let bump = [listing.bump];
+    let signer_seeds = listing.signer_seeds(&bump);
+    transfer_tokens(token_program, escrow_account, seller_nft_account, listing_account, 1, &[&signer_seeds])?;
+    close_token_account(token_program, escrow_account, seller, listing_account, &[&signer_seeds])?;
+    close_program_account(listing_account, seller)?;
+
+    emit(VortexEvent::ExpiredListingClosed {
+        seller: listing.seller,
+        nft_mint: listing.nft_mint,
+    });
+
+    Ok(())
+}
+
+// Load a listing, checking it is this program's PDA
+fn load_listing(program_id: &Pubkey, listing_account: &AccountInfo) -> Result<ArtworkListing, ProgramError> {
+    if listing_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let listing = ArtworkListing::try_from_slice(&listing_account.data.borrow())?;
+    let bump = [listing.bump];
+    let expected = Pubkey::create_program_address(&listing.signer_seeds(&bump), program_id)?;
+    if expected != *listing_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(listing)
+}
+
+// Load the singleton marketplace config
+fn load_config(program_id: &Pubkey, config_account: &AccountInfo) -> Result<MarketplaceConfig, ProgramError> {
+    if config_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let config = MarketplaceConfig::try_from_slice(&config_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(&[CONFIG_SEED, &[config.bump]], program_id)?;
+    if expected != *config_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(config)
+}
+
+// Create the config with the signer as admin
+fn process_initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut config: MarketplaceConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // Whoever deployed the program sets it up, not whoever gets there first
+    if upgrade_authority(program_id, program_data)? != Some(*admin.key) {
+        return Err(VortexError::Unauthorized.into());
+    }
+    let (config_key, config_bump) = Pubkey::find_program_address(&[CONFIG_SEED], program_id);
+    if *config_account.key != config_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !config_account.data_is_empty() {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+
+    config.admin = *admin.key;
+    config.bump = config_bump;
+    config.validate()?;
+
+    create_pda_account(
+        admin,
+        config_account,
+        system_program,
+        program_id,
+        MarketplaceConfig::len(config.accepted_mints.len()),
+        &[CONFIG_SEED, &[config_bump]],
+    )?;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::MarketplaceConfigUpdated {
+        admin: config.admin,
+        fee_bps: config.fee_bps,
+        max_royalty_percentage: config.max_royalty_percentage,
+        paused: config.paused,
+    });
+
+    Ok(())
+}
+
+// Replace the config; only the current admin may
+fn process_update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut config: MarketplaceConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let current = load_config(program_id, config_account)?;
+    if current.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+
+    config.bump = current.bump;
+    config.validate()?;
+
+    resize_account(
+        admin,
+        config_account,
+        system_program,
+        MarketplaceConfig::len(config.accepted_mints.len()),
+    )?;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::MarketplaceConfigUpdated {
+        admin: config.admin,
+        fee_bps: config.fee_bps,
+        max_royalty_percentage: config.max_royalty_percentage,
+        paused: config.paused,
+    });
+
+    Ok(())
+}
+
+// Load an NFT's royalty config, or `None` if it was never registered
+fn load_royalty_config(
+    program_id: &Pubkey,
+    royalty_account: &AccountInfo,
+    nft_mint: &Pubkey,
+) -> Result<Option<RoyaltyConfig>, ProgramError> {
+    let (expected, _) = Pubkey::find_program_address(&[ROYALTY_SEED, nft_mint.as_ref()], program_id);
+    if expected != *royalty_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if royalty_account.data_is_empty() {
+        return Ok(None);
+    }
+    if royalty_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(Some(RoyaltyConfig::try_from_slice(&royalty_account.data.borrow())?))
+}
+
+// Record the creator split for an NFT; only its minter may, and only once
+fn process_register_royalties(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    royalty_percentage: u8,
+    creators: Vec<CreatorShare>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let minter = next_account_info(account_info_iter)?;
+    let royalty_account = next_account_info(account_info_iter)?;
+    let nft_mint = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !minter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *nft_mint.owner != spl_token::id() {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mint = spl_token::state::Mint::unpack(&nft_mint.data.borrow())?;
+    if mint.mint_authority != COption::Some(*minter.key) {
+        return Err(VortexError::Unauthorized.into());
+    }
+
+    let (royalty_key, royalty_bump) =
+        Pubkey::find_program_address(&[ROYALTY_SEED, nft_mint.key.as_ref()], program_id);
+    if *royalty_account.key != royalty_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !royalty_account.data_is_empty() {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+
+    let royalties = RoyaltyConfig {
+        nft_mint: *nft_mint.key,
+        royalty_percentage,
+        bump: royalty_bump,
+        creators,
+    };
+    royalties.validate()?;
+
+    create_pda_account(
+        minter,
+        royalty_account,
+        system_program,
+        program_id,
+        RoyaltyConfig::len(royalties.creators.len()),
+        &[ROYALTY_SEED, nft_mint.key.as_ref(), &[royalty_bump]],
+    )?;
+    royalties.serialize(&mut &mut royalty_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::RoyaltiesRegistered {
+        nft_mint: royalties.nft_mint,
+        royalty_percentage,
+        creators: royalties.creators.iter().map(|creator| creator.address).collect(),
+    });
+
+    Ok(())
+}
+
+// Load an auction, checking it is this program's PDA
+fn load_auction(program_id: &Pubkey, auction_account: &AccountInfo) -> Result<Auction, ProgramError> {
+    if auction_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let auction = Auction::try_from_slice(&auction_account.data.borrow())?;
+    let bump = [auction.bump];
+    let expected = Pubkey::create_program_address(&auction.signer_seeds(&bump), program_id)?;
+    if expected != *auction_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(auction)
+}
+
+// Escrow the NFT and open bidding
+fn process_create_auction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    reserve: u64,
+    start: i64,
+    end: i64,
+    min_increment_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller = next_account_info(account_info_iter)?;
+    let auction_account = next_account_info(account_info_iter)?;
+    let nft_mint = next_account_info(account_info_iter)?;
+    let seller_nft_account = next_account_info(account_info_iter)?;
+    let nft_escrow = next_account_info(account_info_iter)?;
+    let payment_mint = next_account_info(account_info_iter)?;
+    let bid_escrow = next_account_info(account_info_iter)?;
+    let payment_account = next_account_info(account_info_iter)?;
+    let royalty_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !seller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if reserve == 0 || start >= end || end <= now || min_increment_bps > 10_000 {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let config = load_config(program_id, config_account)?;
+    config.assert_not_paused()?;
+
+    // Only single-edition mints can be auctioned
+    if nft_mint.owner != token_program.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mint = spl_token::state::Mint::unpack(&nft_mint.data.borrow())?;
+    if mint.decimals != 0 || mint.supply != 1 {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let royalty_percentage = load_royalty_config(program_id, royalty_account, nft_mint.key)?
+        .map_or(0, |royalties| royalties.royalty_percentage);
+    if royalty_percentage > config.max_royalty_percentage {
+        return Err(VortexError::RoyaltyTooHigh.into());
+    }
+    if payment_account.owner != token_program.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let payment = spl_token::state::Account::unpack(&payment_account.data.borrow())?;
+    if payment.owner != *seller.key || payment.mint != *payment_mint.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !config.accepted_mints.contains(&payment.mint) {
+        return Err(VortexError::PaymentMintNotAccepted.into());
+    }
+
+    let (auction_key, auction_bump) = Pubkey::find_program_address(
+        &[AUCTION_SEED, seller.key.as_ref(), nft_mint.key.as_ref()],
+        program_id,
+    );
+    let (nft_escrow_key, nft_escrow_bump) =
+        Pubkey::find_program_address(&[AUCTION_ESCROW_SEED, auction_key.as_ref()], program_id);
+    let (bid_escrow_key, bid_escrow_bump) =
+        Pubkey::find_program_address(&[BID_ESCROW_SEED, auction_key.as_ref()], program_id);
+    if *auction_account.key != auction_key
+        || *nft_escrow.key != nft_escrow_key
+        || *bid_escrow.key != bid_escrow_key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Like listings, an auction PDA is reused for the next auction of the NFT
+    if auction_account.data_is_empty() {
+        create_pda_account(
+            seller,
+            auction_account,
+            system_program,
+            program_id,
+            Auction::LEN,
+            &[AUCTION_SEED, seller.key.as_ref(), nft_mint.key.as_ref(), &[auction_bump]],
+        )?;
+    } else if Auction::try_from_slice(&auction_account.data.borrow())?.is_active {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+    if nft_escrow.data_is_empty() {
+        create_token_account_pda(
+            seller,
+            nft_escrow,
+            nft_mint,
+            &auction_key,
+            system_program,
+            token_program,
+            &[AUCTION_ESCROW_SEED, auction_key.as_ref(), &[nft_escrow_bump]],
+        )?;
+    }
+    if bid_escrow.data_is_empty() {
+        create_token_account_pda(
+            seller,
+            bid_escrow,
+            payment_mint,
+            &auction_key,
+            system_program,
+            token_program,
+            &[BID_ESCROW_SEED, auction_key.as_ref(), &[bid_escrow_bump]],
+        )?;
+    } else if spl_token::state::Account::unpack(&bid_escrow.data.borrow())?.mint != *payment_mint.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    transfer_tokens(token_program, seller_nft_account, nft_escrow, seller, 1, &[])?;
+
+    let auction = Auction {
+        seller: *seller.key,
+        nft_mint: *nft_mint.key,
+        payment_account: *payment_account.key,
+        reserve,
+        start,
+        end,
+        min_increment_bps,
+        royalty_percentage,
+        highest_bid: 0,
+        highest_bidder: Pubkey::default(),
+        is_active: true,
+        nft_escrow: nft_escrow_key,
+        bid_escrow: bid_escrow_key,
+        bump: auction_bump,
+    };
+    auction.serialize(&mut &mut auction_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::AuctionCreated {
+        auction: auction_key,
+        seller: auction.seller,
+        nft_mint: auction.nft_mint,
+        reserve,
+        end,
+    });
+
+    Ok(())
+}
+
+// Escrow a new high bid and refund the one it beats
+fn process_place_bid(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let bidder = next_account_info(account_info_iter)?;
+    let auction_account = next_account_info(account_info_iter)?;
+    let bidder_token_account = next_account_info(account_info_iter)?;
+    let bid_escrow = next_account_info(account_info_iter)?;
+    let bid_record = next_account_info(account_info_iter)?;
+    let previous_bid_record = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !bidder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_config(program_id, config_account)?.assert_not_paused()?;
+
+    let mut auction = load_auction(program_id, auction_account)?;
+    let now = Clock::get()?.unix_timestamp;
+    if !auction.is_active || now < auction.start || now >= auction.end {
+        return Err(VortexError::AuctionClosed.into());
+    }
+    if auction.bid_escrow != *bid_escrow.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if amount < auction.min_bid() {
+        return Err(VortexError::BidTooLow.into());
+    }
+
+    transfer_tokens(token_program, bidder_token_account, bid_escrow, bidder, amount, &[])?;
+
+    // The outbid amount stays in escrow for its owner to withdraw, so
+    // nothing they do to their own token accounts can block this bid
+    if auction.highest_bid > 0 {
+        let mut previous =
+            load_bid_record(program_id, previous_bid_record, auction_account.key, &auction.highest_bidder)?;
+        previous.refundable = previous
+            .refundable
+            .checked_add(auction.highest_bid)
+            .ok_or(VortexError::MathOverflow)?;
+        previous.serialize(&mut &mut previous_bid_record.data.borrow_mut()[..])?;
+    }
+
+    // The new leader needs a record to be credited to once outbid
+    if bid_record.data_is_empty() {
+        let (record_key, record_bump) = Pubkey::find_program_address(
+            &[BID_RECORD_SEED, auction_account.key.as_ref(), bidder.key.as_ref()],
+            program_id,
+        );
+        if *bid_record.key != record_key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        create_pda_account(
+            bidder,
+            bid_record,
+            system_program,
+            program_id,
+            BidRecord::LEN,
+            &[BID_RECORD_SEED, auction_account.key.as_ref(), bidder.key.as_ref(), &[record_bump]],
+        )?;
+        let record = BidRecord {
+            auction: *auction_account.key,
+            bidder: *bidder.key,
+            refundable: 0,
+            bump: record_bump,
+        };
+        record.serialize(&mut &mut bid_record.data.borrow_mut()[..])?;
+    } else {
+        load_bid_record(program_id, bid_record, auction_account.key, bidder.key)?;
+    }
+
+    auction.highest_bid = amount;
+    auction.highest_bidder = *bidder.key;
+
+    // Anti-sniping: a late bid leaves others a full window to answer
+    if auction.end - now < ANTI_SNIPING_WINDOW {
+        auction.end = now + ANTI_SNIPING_WINDOW;
+    }
+    auction.serialize(&mut &mut auction_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::BidPlaced {
+        auction: *auction_account.key,
+        bidder: *bidder.key,
+        amount,
+        end: auction.end,
+    });
+
+    Ok(())
+}
+
+// Hand the NFT to the winner and pay out the winning bid; anyone may crank this
+fn process_settle_auction(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let auction_account = next_account_info(account_info_iter)?;
+    let nft_escrow = next_account_info(account_info_iter)?;
+    let bid_escrow = next_account_info(account_info_iter)?;
+    let nft_destination = next_account_info(account_info_iter)?;
+    let seller_token_account = next_account_info(account_info_iter)?;
+    let royalty_account = next_account_info(account_info_iter)?;
+    let fee_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let creator_accounts = account_info_iter.as_slice();
+
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let config = load_config(program_id, config_account)?;
+    let mut auction = load_auction(program_id, auction_account)?;
+    if !auction.is_active {
+        return Err(VortexError::InvalidState.into());
+    }
+    if Clock::get()?.unix_timestamp < auction.end {
+        return Err(VortexError::AuctionInProgress.into());
+    }
+    if auction.nft_escrow != *nft_escrow.key
+        || auction.bid_escrow != *bid_escrow.key
+        || auction.payment_account != *seller_token_account.key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Without bids the NFT goes home
+    let recipient = if auction.highest_bid > 0 {
+        auction.highest_bidder
+    } else {
+        auction.seller
+    };
+    if nft_destination.owner != token_program.key
+        || spl_token::state::Account::unpack(&nft_destination.data.borrow())?.owner != recipient
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let bump = [auction.bump];
+    let seeds = auction.signer_seeds(&bump);
+    if auction.highest_bid > 0 {
+        let sale = SaleAccounts {
+            token_program,
+            system_program: None,
+            source: bid_escrow,
+            authority: auction_account,
+            seller_token_account,
+            royalty_account,
+            fee_account,
+            creator_accounts,
+        };
+        settle_sale(
+            program_id,
+            &config,
+            &sale,
+            &auction.nft_mint,
+            auction.highest_bid,
+            auction.royalty_percentage,
+            &[&seeds],
+        )?;
+    }
+    transfer_tokens(token_program, nft_escrow, nft_destination, auction_account, 1, &[&seeds])?;
+
+    auction.is_active = false;
+    auction.serialize(&mut &mut auction_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::AuctionSettled {
+        auction: *auction_account.key,
+        winner: (auction.highest_bid > 0).then_some(auction.highest_bidder),
+        amount: auction.highest_bid,
+    });
+
+    Ok(())
+}
+
+// Load a bid record, checking it is this program's PDA for `bidder` on `auction`
+fn load_bid_record(
+    program_id: &Pubkey,
+    record_account: &AccountInfo,
+    auction: &Pubkey,
+    bidder: &Pubkey,
+) -> Result<BidRecord, ProgramError> {
+    if record_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let record = BidRecord::try_from_slice(&record_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(
+        &[BID_RECORD_SEED, auction.as_ref(), bidder.as_ref(), &[record.bump]],
+        program_id,
+    )
+    .map_err(|_| VortexError::InvalidAccount)?;
+    if expected != *record_account.key || record.auction != *auction || record.bidder != *bidder {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(record)
+}
+
+// Pay a bidder back what they were outbid with, or the bid they lead with once settlement is overdue
+fn process_withdraw_bid_refund(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let bidder = next_account_info(account_info_iter)?;
+    let auction_account = next_account_info(account_info_iter)?;
+    let bid_record = next_account_info(account_info_iter)?;
+    let bid_escrow = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !bidder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut auction = load_auction(program_id, auction_account)?;
+    let mut record = load_bid_record(program_id, bid_record, auction_account.key, bidder.key)?;
+    if auction.bid_escrow != *bid_escrow.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut amount = record.refundable;
+
+    // A settlement that keeps failing must not lock the leader's bid up forever
+    let mut leading = auction.is_active && auction.highest_bid > 0 && auction.highest_bidder == *bidder.key;
+    let settlement_deadline = auction.end.checked_add(SETTLEMENT_GRACE_PERIOD).ok_or(VortexError::MathOverflow)?;
+    if leading && Clock::get()?.unix_timestamp > settlement_deadline {
+        amount = amount.checked_add(auction.highest_bid).ok_or(VortexError::MathOverflow)?;
+        auction.highest_bid = 0;
+        auction.highest_bidder = Pubkey::default();
+        auction.serialize(&mut &mut auction_account.data.borrow_mut()[..])?;
+        leading = false;
+    }
+    if amount == 0 {
+        return Err(VortexError::NothingToClaim.into());
+    }
+
+    let bump = [auction.bump];
+    transfer_tokens(
+        token_program,
+        bid_escrow,
+        destination,
+        auction_account,
+        amount,
+        &[&auction.signer_seeds(&bump)],
+    )?;
+
+    // The leader's record must stay so it can be credited when they are outbid
+    if leading {
+        record.refundable = 0;
+        record.serialize(&mut &mut bid_record.data.borrow_mut()[..])?;
+    } else {
+        close_program_account(bid_record, bidder)?;
+    }
+
+    emit(VortexEvent::BidRefundWithdrawn {
+        auction: *auction_account.key,
+        bidder: *bidder.key,
+        amount,
+    });
+
+    Ok(())
+}
+
+// Withdraw an auction before anyone has bid
+fn process_cancel_auction(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller = next_account_info(account_info_iter)?;
+    let auction_account = next_account_info(account_info_iter)?;
+    let nft_escrow = next_account_info(account_info_iter)?;
+    let seller_nft_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !seller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut auction = load_auction(program_id, auction_account)?;
+    if auction.seller != *seller.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if !auction.is_active || auction.highest_bid > 0 {
+        return Err(VortexError::InvalidState.into());
+    }
+    if auction.nft_escrow != *nft_escrow.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let bump = [auction.bump];
+    transfer_tokens(
+        token_program,
+        nft_escrow,
+        seller_nft_account,
+        auction_account,
+        1,
+        &[&auction.signer_seeds(&bump)],
+    )?;
+
+    auction.is_active = false;
+    auction.serialize(&mut &mut auction_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::AuctionCancelled {
+        auction: *auction_account.key,
+    });
+
+    Ok(())
+}
+
+// Load an offer, checking it is this program's PDA
+fn load_offer(program_id: &Pubkey, offer_account: &AccountInfo) -> Result<Offer, ProgramError> {
+    if offer_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let offer = Offer::try_from_slice(&offer_account.data.borrow())?;
+    let bump = [offer.bump];
+    let expected = Pubkey::create_program_address(&offer.signer_seeds(&bump), program_id)?;
+    if expected != *offer_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(offer)
+}
+
+// Escrow the buyer's payment behind a standing offer
+fn process_make_offer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    nft_mint: Pubkey,
+    amount: u64,
+    expiry: i64,
+    is_collection: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer = next_account_info(account_info_iter)?;
+    let offer_account = next_account_info(account_info_iter)?;
+    let buyer_token_account = next_account_info(account_info_iter)?;
+    let payment_mint = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !buyer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if is_collection {
+        let collection_account = next_account_info(account_info_iter)?;
+        load_collection(program_id, collection_account)?;
+        if *collection_account.key != nft_mint {
+            return Err(VortexError::InvalidAccount.into());
+        }
+    }
+    if amount == 0 || expiry <= Clock::get()?.unix_timestamp {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let config = load_config(program_id, config_account)?;
+    config.assert_not_paused()?;
+    // Offers sit in token escrow, so they cannot be made in SOL
+    if *payment_mint.key == NATIVE_SOL_MINT || !config.accepted_mints.contains(payment_mint.key) {
+        return Err(VortexError::PaymentMintNotAccepted.into());
+    }
+
+    let (offer_key, offer_bump) =
+        Pubkey::find_program_address(&[OFFER_SEED, buyer.key.as_ref(), nft_mint.as_ref()], program_id);
+    let (escrow_key, escrow_bump) =
+        Pubkey::find_program_address(&[OFFER_ESCROW_SEED, offer_key.as_ref()], program_id);
+    if *offer_account.key != offer_key || *escrow_account.key != escrow_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // One live offer per buyer and NFT; the PDA is reused once it closes
+    if offer_account.data_is_empty() {
+        create_pda_account(
+            buyer,
+            offer_account,
+            system_program,
+            program_id,
+            Offer::LEN,
+            &[OFFER_SEED, buyer.key.as_ref(), nft_mint.as_ref(), &[offer_bump]],
+        )?;
+    } else if Offer::try_from_slice(&offer_account.data.borrow())?.is_active {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+    if escrow_account.data_is_empty() {
+        create_token_account_pda(
+            buyer,
+            escrow_account,
+            payment_mint,
+            &offer_key,
+            system_program,
+            token_program,
+            &[OFFER_ESCROW_SEED, offer_key.as_ref(), &[escrow_bump]],
+        )?;
+    } else if spl_token::state::Account::unpack(&escrow_account.data.borrow())?.mint != *payment_mint.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    transfer_tokens(token_program, buyer_token_account, escrow_account, buyer, amount, &[])?;
+
+    let offer = Offer {
+        buyer: *buyer.key,
+        nft_mint,
+        amount,
+        expiry,
+        refund_account: *buyer_token_account.key,
+        is_active: true,
+        escrow: escrow_key,
+        bump: offer_bump,
+        is_collection,
+    };
+    offer.serialize(&mut &mut offer_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::OfferMade {
+        offer: offer_key,
+        buyer: offer.buyer,
+        nft_mint,
+        amount,
+        expiry,
+    });
+
+    Ok(())
+}
+
+// Swap the holder's NFT for the escrowed offer
+fn process_accept_offer(program_id: &Pubkey, accounts: &[AccountInfo], is_collection: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let holder = next_account_info(account_info_iter)?;
+    let offer_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let holder_nft_account = next_account_info(account_info_iter)?;
+    let buyer_nft_account = next_account_info(account_info_iter)?;
+    let holder_token_account = next_account_info(account_info_iter)?;
+    let member_account = if is_collection {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+    let royalty_account = next_account_info(account_info_iter)?;
+    let fee_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let creator_accounts = account_info_iter.as_slice();
+
+    if !holder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = load_config(program_id, config_account)?;
+    config.assert_not_paused()?;
+
+    let mut offer = load_offer(program_id, offer_account)?;
+    if !offer.is_active {
+        return Err(VortexError::InvalidState.into());
+    }
+    if Clock::get()?.unix_timestamp >= offer.expiry {
+        return Err(VortexError::OfferExpired.into());
+    }
+    if offer.escrow != *escrow_account.key || offer.is_collection != is_collection {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Collection offers take any member, proven by its member account
+    let nft_mint = match member_account {
+        Some(member_account) => {
+            let member = load_collection_member(program_id, member_account)?;
+            if member.collection != offer.nft_mint {
+                return Err(VortexError::InvalidAccount.into());
+            }
+            member.nft_mint
+        }
+        None => offer.nft_mint,
+    };
+
+    // The buyer must end up with the NFT and the holder with the proceeds
+    let token_account = |account: &AccountInfo| -> Result<spl_token::state::Account, ProgramError> {
+        if account.owner != token_program.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        spl_token::state::Account::unpack(&account.data.borrow())
+    };
+    let buyer_nft = token_account(buyer_nft_account)?;
+    if buyer_nft.owner != offer.buyer
+        || buyer_nft.mint != nft_mint
+        || token_account(holder_token_account)?.owner != *holder.key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let royalty_percentage = load_royalty_config(program_id, royalty_account, &nft_mint)?
+        .map_or(0, |royalties| royalties.royalty_percentage);
+    if royalty_percentage > config.max_royalty_percentage {
+        return Err(VortexError::RoyaltyTooHigh.into());
+    }
+
+    let bump = [offer.bump];
+    let sale = SaleAccounts {
+        token_program,
+        system_program: None,
+        source: escrow_account,
+        authority: offer_account,
+        seller_token_account: holder_token_account,
+        royalty_account,
+        fee_account,
+        creator_accounts,
+    };
+    settle_sale(
+        program_id,
+        &config,
+        &sale,
+        &nft_mint,
+        offer.amount,
+        royalty_percentage,
+        &[&offer.signer_seeds(&bump)],
+    )?;
+    transfer_tokens(token_program, holder_nft_account, buyer_nft_account, holder, 1, &[])?;
+
+    offer.is_active = false;
+    offer.serialize(&mut &mut offer_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::OfferAccepted {
+        offer: *offer_account.key,
+        seller: *holder.key,
+        buyer: offer.buyer,
+        nft_mint,
+        amount: offer.amount,
+    });
+
+    Ok(())
+}
+
+// Refund an offer to its buyer
+fn process_cancel_offer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer = next_account_info(account_info_iter)?;
+    let offer_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let refund_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !buyer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut offer = load_offer(program_id, offer_account)?;
+    if offer.buyer != *buyer.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if !offer.is_active {
+        return Err(VortexError::InvalidState.into());
+    }
+    if offer.escrow != *escrow_account.key || offer.refund_account != *refund_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let bump = [offer.bump];
+    transfer_tokens(
+        token_program,
+        escrow_account,
+        refund_account,
+        offer_account,
+        offer.amount,
+        &[&offer.signer_seeds(&bump)],
+    )?;
+
+    offer.is_active = false;
+    offer.serialize(&mut &mut offer_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::OfferCancelled {
+        offer: *offer_account.key,
+    });
+
+    Ok(())
+}
+
+// Load a collection, checking it is this program's PDA
+fn load_collection(program_id: &Pubkey, collection_account: &AccountInfo) -> Result<Collection, ProgramError> {
+    if collection_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let collection = Collection::try_from_slice(&collection_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(
+        &[COLLECTION_SEED, collection.collection_key.as_ref(), &[collection.bump]],
+        program_id,
+    )?;
+    if expected != *collection_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(collection)
+}
+
+// Load a collection member record, checking it is this program's PDA
+fn load_collection_member(
+    program_id: &Pubkey,
+    member_account: &AccountInfo,
+) -> Result<CollectionMember, ProgramError> {
+    if member_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let member = CollectionMember::try_from_slice(&member_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(
+        &[
+            COLLECTION_MEMBER_SEED,
+            member.collection.as_ref(),
+            member.nft_mint.as_ref(),
+            &[member.bump],
+        ],
+        program_id,
+    )?;
+    if expected != *member_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(member)
+}
+
+fn process_create_collection(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority = next_account_info(account_info_iter)?;
+    let collection_key = next_account_info(account_info_iter)?;
+    let collection_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    // Signing with the collection key stops anyone squatting on it
+    if !authority.is_signer || !collection_key.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (collection_pda, bump) =
+        Pubkey::find_program_address(&[COLLECTION_SEED, collection_key.key.as_ref()], program_id);
+    if *collection_account.key != collection_pda {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !collection_account.data_is_empty() {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+
+    create_pda_account(
+        authority,
+        collection_account,
+        system_program,
+        program_id,
+        Collection::LEN,
+        &[COLLECTION_SEED, collection_key.key.as_ref(), &[bump]],
+    )?;
+    let collection = Collection {
+        authority: *authority.key,
+        collection_key: *collection_key.key,
+        num_members: 0,
+        bump,
+    };
+    collection.serialize(&mut &mut collection_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::CollectionCreated {
+        collection: collection_pda,
+        authority: collection.authority,
+    });
+
+    Ok(())
+}
+
+fn process_verify_collection_member(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority = next_account_info(account_info_iter)?;
+    let collection_account = next_account_info(account_info_iter)?;
+    let nft_mint = next_account_info(account_info_iter)?;
+    let member_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut collection = load_collection(program_id, collection_account)?;
+    if collection.authority != *authority.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+
+    // Only single-edition NFTs can join a collection, and membership is
+    // never re-checked, so the supply has to be fixed for good
+    if *nft_mint.owner != spl_token::id() {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mint = spl_token::state::Mint::unpack(&nft_mint.data.borrow())?;
+    if mint.decimals != 0 || mint.supply != 1 || mint.mint_authority.is_some() {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let (member_key, bump) = Pubkey::find_program_address(
+        &[COLLECTION_MEMBER_SEED, collection_account.key.as_ref(), nft_mint.key.as_ref()],
+        program_id,
+    );
+    if *member_account.key != member_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if !member_account.data_is_empty() {
+        return Err(VortexError::AlreadyInitialized.into());
+    }
+
+    create_pda_account(
+        authority,
+        member_account,
+        system_program,
+        program_id,
+        CollectionMember::LEN,
+        &[COLLECTION_MEMBER_SEED, collection_account.key.as_ref(), nft_mint.key.as_ref(), &[bump]],
+    )?;
+    let member = CollectionMember {
+        collection: *collection_account.key,
+        nft_mint: *nft_mint.key,
+        bump,
+    };
+    member.serialize(&mut &mut member_account.data.borrow_mut()[..])?;
+
+    collection.num_members = collection.num_members.checked_add(1).ok_or(VortexError::MathOverflow)?;
+    collection.serialize(&mut &mut collection_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::CollectionMemberVerified {
+        collection: member.collection,
+        nft_mint: member.nft_mint,
+    });
+
+    Ok(())
+}
+
+// A listing passed to `SweepFloor` along with the accounts needed to buy it
+struct SweepCandidate<'a, 'b> {
+    listing_account: &'b AccountInfo<'a>,
+    listing: ArtworkListing,
+    escrow_account: &'b AccountInfo<'a>,
+    seller_token_account: &'b AccountInfo<'a>,
+    buyer_nft_account: &'b AccountInfo<'a>,
+    royalty_account: &'b AccountInfo<'a>,
+    creator_accounts: &'b [AccountInfo<'a>],
+    price: u64,
+}
+
+fn process_sweep_floor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_count: u8,
+    max_total: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer = next_account_info(account_info_iter)?;
+    let buyer_token_account = next_account_info(account_info_iter)?;
+    let collection_account = next_account_info(account_info_iter)?;
+    let fee_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !buyer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = load_config(program_id, config_account)?;
+    config.assert_not_paused()?;
+    load_collection(program_id, collection_account)?;
+
+    // The budget is in one currency, set by what the buyer pays from
+    let payment_mint = if buyer_token_account.key == buyer.key {
+        NATIVE_SOL_MINT
+    } else {
+        if buyer_token_account.owner != token_program.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        spl_token::state::Account::unpack(&buyer_token_account.data.borrow())?.mint
+    };
+
+    // Gather the purchasable listings; ones sold, cancelled or expired since
+    // the transaction was built are skipped rather than failing the sweep
+    let now = Clock::get()?.unix_timestamp;
+    let mut candidates = Vec::new();
+    let mut remaining = account_info_iter.as_slice();
+    while !remaining.is_empty() {
+        let candidate_iter = &mut remaining.iter();
+        let listing_account = next_account_info(candidate_iter)?;
+        let escrow_account = next_account_info(candidate_iter)?;
+        let seller_token_account = next_account_info(candidate_iter)?;
+        let buyer_nft_account = next_account_info(candidate_iter)?;
+        let member_account = next_account_info(candidate_iter)?;
+        let royalty_account = next_account_info(candidate_iter)?;
+        let rest = candidate_iter.as_slice();
+
+        let member = load_collection_member(program_id, member_account)?;
+        if member.collection != *collection_account.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        let num_creators = load_royalty_config(program_id, royalty_account, &member.nft_mint)?
+            .map_or(0, |royalties| royalties.creators.len());
+        if rest.len() < num_creators {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (creator_accounts, rest) = rest.split_at(num_creators);
+        remaining = rest;
+
+        // A listing closed since, e.g. by `CloseExpiredListing`, is gone
+        if listing_account.data_is_empty() || listing_account.owner != program_id {
+            continue;
+        }
+        let listing = load_listing(program_id, listing_account)?;
+        if member.nft_mint != listing.nft_mint || listing.payment_mint != payment_mint {
+            return Err(VortexError::InvalidAccount.into());
+        }
+
+        let started = listing.kind != ListingKind::DutchAuction || now >= listing.dutch_auction.start_time;
+        if listing.is_active && started && !listing.is_expired(now) {
+            candidates.push(SweepCandidate {
+                price: listing.current_price(now)?,
+                listing_account,
+                listing,
+                escrow_account,
+                seller_token_account,
+                buyer_nft_account,
+                royalty_account,
+                creator_accounts,
+            });
+        }
+    }
+    candidates.sort_by_key(|candidate| candidate.price);
+
+    let mut count: u8 = 0;
+    let mut total: u64 = 0;
+    for candidate in candidates {
+        if count == max_count {
+            break;
+        }
+        let next_total = total.checked_add(candidate.price).ok_or(VortexError::MathOverflow)?;
+        if next_total > max_total {
+            break;
+        }
+
+        let sale = SaleAccounts {
+            token_program,
+            system_program: (payment_mint == NATIVE_SOL_MINT).then_some(system_program),
+            source: buyer_token_account,
+            authority: buyer,
+            seller_token_account: candidate.seller_token_account,
+            royalty_account: candidate.royalty_account,
+            fee_account,
+            creator_accounts: candidate.creator_accounts,
+        };
+        complete_purchase(
+            program_id,
+            &config,
+            &sale,
+            candidate.listing_account,
+            candidate.listing,
+            candidate.escrow_account,
+            candidate.buyer_nft_account,
+            candidate.price,
+        )?;
+        count += 1;
+        total = next_total;
+    }
+
+    emit(VortexEvent::FloorSwept {
+        collection: *collection_account.key,
+        buyer: *buyer.key,
+        count,
+        total,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,1941 @@
+use super::*;
+use crate::tests::helpers::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_option::COption,
+    system_program,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+fn program_test(program_id: Pubkey) -> ProgramTest {
+    ProgramTest::new(
+        "vortex_contracts",
+        program_id,
+        processor!(process_instruction),
+    )
+}
+
+fn config_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id).0
+}
+
+fn royalty_address(program_id: &Pubkey, nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ROYALTY_SEED, nft_mint.as_ref()], program_id).0
+}
+
+fn auction_address(program_id: &Pubkey, seller: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+    let (auction, _) =
+        Pubkey::find_program_address(&[AUCTION_SEED, seller.as_ref(), nft_mint.as_ref()], program_id);
+    let (nft_escrow, _) = Pubkey::find_program_address(&[AUCTION_ESCROW_SEED, auction.as_ref()], program_id);
+    let (bid_escrow, _) = Pubkey::find_program_address(&[BID_ESCROW_SEED, auction.as_ref()], program_id);
+    (auction, nft_escrow, bid_escrow)
+}
+
+fn listing_address(program_id: &Pubkey, seller: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, Pubkey) {
+    let (listing, _) =
+        Pubkey::find_program_address(&[LISTING_SEED, seller.as_ref(), nft_mint.as_ref()], program_id);
+    let (escrow, _) = Pubkey::find_program_address(&[ESCROW_SEED, listing.as_ref()], program_id);
+    (listing, escrow)
+}
+
+fn list_artwork_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_nft_account: &Pubkey,
+    payment_account: &Pubkey,
+    price: u64,
+    royalty_percentage: u8,
+) -> Instruction {
+    let (listing, escrow) = listing_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::ListArtwork { price, royalty_percentage }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new(listing, false),
+            AccountMeta::new_readonly(*nft_mint, false),
+            AccountMeta::new(*seller_nft_account, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false),
+            AccountMeta::new_readonly(*payment_account, false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+        ],
+    )
+}
+
+fn list_dutch_auction_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_nft_account: &Pubkey,
+    payment_account: &Pubkey,
+    terms: DutchAuctionTerms,
+) -> Instruction {
+    let mut instruction = list_artwork_instruction(program_id, seller, nft_mint, seller_nft_account, payment_account, 0, 0);
+    instruction.data = MarketplaceInstruction::ListDutchAuction { terms, royalty_percentage: 0 }
+        .try_to_vec()
+        .unwrap();
+    instruction
+}
+
+#[allow(clippy::too_many_arguments)]
+fn purchase_artwork_instruction(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_token_account: &Pubkey,
+    buyer_token_account: &Pubkey,
+    buyer_nft_account: &Pubkey,
+    creator_token_accounts: &[Pubkey],
+    fee_account: &Pubkey,
+    price: u64,
+) -> Instruction {
+    let (listing, escrow) = listing_address(program_id, seller, nft_mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(*buyer, true),
+        AccountMeta::new(listing, false),
+        AccountMeta::new(*seller_token_account, false),
+        AccountMeta::new(*buyer_token_account, false),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(*buyer_nft_account, false),
+        AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false),
+        AccountMeta::new(*fee_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(config_address(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(creator_token_accounts.iter().map(|account| AccountMeta::new(*account, false)));
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::PurchaseArtwork { price }.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+fn register_royalties_instruction(
+    program_id: &Pubkey,
+    minter: &Pubkey,
+    nft_mint: &Pubkey,
+    royalty_percentage: u8,
+    creators: Vec<CreatorShare>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::RegisterRoyalties { royalty_percentage, creators }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*minter, true),
+            AccountMeta::new(royalty_address(program_id, nft_mint), false),
+            AccountMeta::new_readonly(*nft_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn initialize_config_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    fee_recipient: &Pubkey,
+    accepted_mints: Vec<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::InitializeConfig {
+            fee_bps: 250,
+            fee_recipient: *fee_recipient,
+            max_royalty_percentage: 50,
+            accepted_mints,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_address(program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+    )
+}
+
+fn update_config_instruction(program_id: &Pubkey, signer: &Pubkey, config: &MarketplaceConfig) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::UpdateConfig {
+            admin: config.admin,
+            fee_bps: config.fee_bps,
+            fee_recipient: config.fee_recipient,
+            max_royalty_percentage: config.max_royalty_percentage,
+            accepted_mints: config.accepted_mints.clone(),
+            paused: config.paused,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*signer, true),
+            AccountMeta::new(config_address(program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_auction_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_nft_account: &Pubkey,
+    payment_mint: &Pubkey,
+    payment_account: &Pubkey,
+    reserve: u64,
+    start: i64,
+    end: i64,
+) -> Instruction {
+    let (auction, nft_escrow, bid_escrow) = auction_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::CreateAuction { reserve, start, end, min_increment_bps: 500 }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new(auction, false),
+            AccountMeta::new_readonly(*nft_mint, false),
+            AccountMeta::new(*seller_nft_account, false),
+            AccountMeta::new(nft_escrow, false),
+            AccountMeta::new_readonly(*payment_mint, false),
+            AccountMeta::new(bid_escrow, false),
+            AccountMeta::new_readonly(*payment_account, false),
+            AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn bid_record_address(program_id: &Pubkey, auction: &Pubkey, bidder: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BID_RECORD_SEED, auction.as_ref(), bidder.as_ref()], program_id).0
+}
+
+fn place_bid_instruction(
+    program_id: &Pubkey,
+    auction: &Pubkey,
+    bidder: &Pubkey,
+    bidder_token_account: &Pubkey,
+    previous_bidder: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (bid_escrow, _) = Pubkey::find_program_address(&[BID_ESCROW_SEED, auction.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::PlaceBid { amount }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*bidder, true),
+            AccountMeta::new(*auction, false),
+            AccountMeta::new(*bidder_token_account, false),
+            AccountMeta::new(bid_escrow, false),
+            AccountMeta::new(bid_record_address(program_id, auction, bidder), false),
+            AccountMeta::new(bid_record_address(program_id, auction, previous_bidder), false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn withdraw_bid_refund_instruction(
+    program_id: &Pubkey,
+    auction: &Pubkey,
+    bidder: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (bid_escrow, _) = Pubkey::find_program_address(&[BID_ESCROW_SEED, auction.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::WithdrawBidRefund {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*bidder, true),
+            AccountMeta::new(*auction, false),
+            AccountMeta::new(bid_record_address(program_id, auction, bidder), false),
+            AccountMeta::new(bid_escrow, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn settle_auction_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    nft_destination: &Pubkey,
+    seller_token_account: &Pubkey,
+    fee_account: &Pubkey,
+) -> Instruction {
+    let (auction, nft_escrow, bid_escrow) = auction_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::SettleAuction {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(auction, false),
+            AccountMeta::new(nft_escrow, false),
+            AccountMeta::new(bid_escrow, false),
+            AccountMeta::new(*nft_destination, false),
+            AccountMeta::new(*seller_token_account, false),
+            AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false),
+            AccountMeta::new(*fee_account, false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn cancel_auction_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_nft_account: &Pubkey,
+) -> Instruction {
+    let (auction, nft_escrow, _) = auction_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::CancelAuction {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*seller, true),
+            AccountMeta::new(auction, false),
+            AccountMeta::new(nft_escrow, false),
+            AccountMeta::new(*seller_nft_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn cancel_listing_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_nft_account: &Pubkey,
+) -> Instruction {
+    let (listing, escrow) = listing_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::CancelListing {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*seller, true),
+            AccountMeta::new(listing, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(*seller_nft_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+// Sign with the payer and any extra signers against a fresh blockhash
+fn update_listing_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    new_price: u64,
+    new_expiry: i64,
+) -> Instruction {
+    let (listing, _) = listing_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::UpdateListing { new_price, new_expiry }.try_to_vec().unwrap(),
+        vec![AccountMeta::new_readonly(*seller, true), AccountMeta::new(listing, false)],
+    )
+}
+
+fn close_expired_listing_instruction(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    nft_mint: &Pubkey,
+    seller_nft_account: &Pubkey,
+) -> Instruction {
+    let (listing, escrow) = listing_address(program_id, seller, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::CloseExpiredListing {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(listing, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(*seller_nft_account, false),
+            AccountMeta::new(*seller, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn offer_address(program_id: &Pubkey, buyer: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, Pubkey) {
+    let (offer, _) = Pubkey::find_program_address(&[OFFER_SEED, buyer.as_ref(), nft_mint.as_ref()], program_id);
+    let (escrow, _) = Pubkey::find_program_address(&[OFFER_ESCROW_SEED, offer.as_ref()], program_id);
+    (offer, escrow)
+}
+
+fn make_offer_instruction(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    nft_mint: &Pubkey,
+    buyer_token_account: &Pubkey,
+    payment_mint: &Pubkey,
+    amount: u64,
+    expiry: i64,
+) -> Instruction {
+    let (offer, escrow) = offer_address(program_id, buyer, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::MakeOffer { nft_mint: *nft_mint, amount, expiry }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*buyer, true),
+            AccountMeta::new(offer, false),
+            AccountMeta::new(*buyer_token_account, false),
+            AccountMeta::new_readonly(*payment_mint, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(config_address(program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn accept_offer_instruction(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    buyer: &Pubkey,
+    nft_mint: &Pubkey,
+    holder_nft_account: &Pubkey,
+    buyer_nft_account: &Pubkey,
+    holder_token_account: &Pubkey,
+    creator_token_accounts: &[Pubkey],
+    fee_account: &Pubkey,
+) -> Instruction {
+    let (offer, escrow) = offer_address(program_id, buyer, nft_mint);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*holder, true),
+        AccountMeta::new(offer, false),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(*holder_nft_account, false),
+        AccountMeta::new(*buyer_nft_account, false),
+        AccountMeta::new(*holder_token_account, false),
+        AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false),
+        AccountMeta::new(*fee_account, false),
+        AccountMeta::new_readonly(config_address(program_id), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(creator_token_accounts.iter().map(|account| AccountMeta::new(*account, false)));
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::AcceptOffer {}.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+fn cancel_offer_instruction(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    nft_mint: &Pubkey,
+    refund_account: &Pubkey,
+) -> Instruction {
+    let (offer, escrow) = offer_address(program_id, buyer, nft_mint);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::CancelOffer {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*buyer, true),
+            AccountMeta::new(offer, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(*refund_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn collection_address(program_id: &Pubkey, collection_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[COLLECTION_SEED, collection_key.as_ref()], program_id).0
+}
+
+fn member_address(program_id: &Pubkey, collection: &Pubkey, nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[COLLECTION_MEMBER_SEED, collection.as_ref(), nft_mint.as_ref()], program_id).0
+}
+
+fn create_collection_instruction(program_id: &Pubkey, authority: &Pubkey, collection_key: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::CreateCollection {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*collection_key, true),
+            AccountMeta::new(collection_address(program_id, collection_key), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn verify_collection_member_instruction(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    collection: &Pubkey,
+    nft_mint: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::VerifyCollectionMember {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*collection, false),
+            AccountMeta::new_readonly(*nft_mint, false),
+            AccountMeta::new(member_address(program_id, collection, nft_mint), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn make_collection_offer_instruction(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    collection: &Pubkey,
+    buyer_token_account: &Pubkey,
+    payment_mint: &Pubkey,
+    amount: u64,
+    expiry: i64,
+) -> Instruction {
+    let mut instruction =
+        make_offer_instruction(program_id, buyer, collection, buyer_token_account, payment_mint, amount, expiry);
+    instruction.data = MarketplaceInstruction::MakeCollectionOffer { collection: *collection, amount, expiry }
+        .try_to_vec()
+        .unwrap();
+    instruction.accounts.push(AccountMeta::new_readonly(*collection, false));
+    instruction
+}
+
+#[allow(clippy::too_many_arguments)]
+fn accept_collection_offer_instruction(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    buyer: &Pubkey,
+    collection: &Pubkey,
+    nft_mint: &Pubkey,
+    holder_nft_account: &Pubkey,
+    buyer_nft_account: &Pubkey,
+    holder_token_account: &Pubkey,
+    fee_account: &Pubkey,
+) -> Instruction {
+    let mut instruction = accept_offer_instruction(
+        program_id,
+        holder,
+        buyer,
+        collection,
+        holder_nft_account,
+        buyer_nft_account,
+        holder_token_account,
+        &[],
+        fee_account,
+    );
+    instruction.data = MarketplaceInstruction::AcceptCollectionOffer {}.try_to_vec().unwrap();
+    instruction.accounts[6] = AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false);
+    instruction
+        .accounts
+        .insert(6, AccountMeta::new_readonly(member_address(program_id, collection, nft_mint), false));
+    instruction
+}
+
+// Each candidate is (seller, nft_mint, seller_token_account, buyer_nft_account)
+#[allow(clippy::too_many_arguments)]
+fn sweep_floor_instruction(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    buyer_token_account: &Pubkey,
+    collection: &Pubkey,
+    fee_account: &Pubkey,
+    candidates: &[(Pubkey, Pubkey, Pubkey, Pubkey)],
+    max_count: u8,
+    max_total: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*buyer, true),
+        AccountMeta::new(*buyer_token_account, false),
+        AccountMeta::new_readonly(*collection, false),
+        AccountMeta::new(*fee_account, false),
+        AccountMeta::new_readonly(config_address(program_id), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (seller, nft_mint, seller_token_account, buyer_nft_account) in candidates {
+        let (listing, escrow) = listing_address(program_id, seller, nft_mint);
+        accounts.extend([
+            AccountMeta::new(listing, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(*seller_token_account, false),
+            AccountMeta::new(*buyer_nft_account, false),
+            AccountMeta::new_readonly(member_address(program_id, collection, nft_mint), false),
+            AccountMeta::new_readonly(royalty_address(program_id, nft_mint), false),
+        ]);
+    }
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &MarketplaceInstruction::SweepFloor { max_count, max_total }.try_to_vec().unwrap(),
+        accounts,
+    )
+}
+
+// Mint a one-of-one NFT held by its minter `owner`; returns the mint and the holding account
+fn create_nft(context: &mut ProgramTestContext, owner: &Pubkey) -> (Pubkey, Pubkey) {
+    let mint = Pubkey::new_unique();
+    set_packed_account(
+        context,
+        &mint,
+        spl_token::state::Mint {
+            mint_authority: COption::Some(*owner),
+            supply: 1,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+    );
+    let account = create_token_account(context, &mint, owner, 1);
+    (mint, account)
+}
+
+// Drop an NFT's mint authority so no further editions can be minted
+fn revoke_mint_authority(context: &mut ProgramTestContext, mint: &Pubkey) {
+    set_packed_account(
+        context,
+        mint,
+        spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: 1,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+    );
+}
+
+fn create_payment_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    set_packed_account(
+        context,
+        &mint,
+        spl_token::state::Mint {
+            supply: u64::MAX,
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        },
+    );
+    mint
+}
+
+// Set up the config with the payer as upgrade authority, hence admin: 2.5% fee, royalties up to 50%
+async fn initialize_config(context: &mut ProgramTestContext, program_id: &Pubkey, accepted_mints: Vec<Pubkey>) -> Pubkey {
+    let admin = context.payer.pubkey();
+    set_upgrade_authority(context, program_id, &admin);
+    let fee_recipient = Pubkey::new_unique();
+    process(
+        context,
+        &[initialize_config_instruction(program_id, &admin, &fee_recipient, accepted_mints)],
+        &[],
+    )
+    .await
+    .unwrap();
+    fee_recipient
+}
+
+async fn config(context: &mut ProgramTestContext, program_id: &Pubkey) -> MarketplaceConfig {
+    let account = context.banks_client.get_account(config_address(program_id)).await.unwrap().unwrap();
+    MarketplaceConfig::try_from_slice(&account.data).unwrap()
+}
+
+async fn lamports(context: &mut ProgramTestContext, wallet: &Pubkey) -> u64 {
+    context.banks_client.get_balance(*wallet).await.unwrap()
+}
+
+async fn listing(context: &mut ProgramTestContext, address: &Pubkey) -> ArtworkListing {
+    let account = context.banks_client.get_account(*address).await.unwrap().unwrap();
+    ArtworkListing::try_from_slice(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_list_and_cancel_escrows_nft() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let (listing_key, escrow) = listing_address(&program_id, &seller.pubkey(), &nft_mint);
+    let payment_mint = create_payment_mint(&mut context);
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+
+    process(
+        &mut context,
+        &[list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, 500, 0)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &seller_nft).await, 0);
+    assert_eq!(token_balance(&mut context, &escrow).await, 1);
+    let listed = listing(&mut context, &listing_key).await;
+    assert!(listed.is_active);
+    assert_eq!(listed.price, 500);
+    assert_eq!(listed.escrow, escrow);
+
+    // Nobody else can cancel
+    let attacker = Keypair::new();
+    let attacker_nft = create_token_account(&mut context, &nft_mint, &attacker.pubkey(), 0);
+    let mut instruction = cancel_listing_instruction(&program_id, &seller.pubkey(), &nft_mint, &attacker_nft);
+    instruction.accounts[0] = AccountMeta::new_readonly(attacker.pubkey(), true);
+    let err = process(&mut context, &[instruction], &[&attacker]).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::Unauthorized as u32))
+    );
+
+    process(
+        &mut context,
+        &[cancel_listing_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &seller_nft).await, 1);
+    assert_eq!(token_balance(&mut context, &escrow).await, 0);
+    assert!(!listing(&mut context, &listing_key).await.is_active);
+
+    // The same PDA is reused when relisting
+    process(
+        &mut context,
+        &[list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, 700, 0)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    assert_eq!(listing(&mut context, &listing_key).await.price, 700);
+}
+
+#[tokio::test]
+async fn test_list_rejects_fungible_mint() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+
+    let mint = Pubkey::new_unique();
+    set_packed_account(
+        &mut context,
+        &mint,
+        spl_token::state::Mint {
+            supply: 1_000,
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        },
+    );
+    let seller_tokens = create_token_account(&mut context, &mint, &seller.pubkey(), 1_000);
+    initialize_config(&mut context, &program_id, vec![mint]).await;
+
+    let err = process(
+        &mut context,
+        &[list_artwork_instruction(&program_id, &seller.pubkey(), &mint, &seller_tokens, &seller_tokens, 500, 0)],
+        &[&seller],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::InvalidAccount as u32))
+    );
+}
+
+#[tokio::test]
+async fn test_purchase_splits_payment() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let buyer = Keypair::new();
+    let artist = Pubkey::new_unique();
+    let prompt_engineer = Pubkey::new_unique();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let (listing_key, _) = listing_address(&program_id, &seller.pubkey(), &nft_mint);
+
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let buyer_tokens = create_token_account(&mut context, &payment_mint, &buyer.pubkey(), 100_000);
+    let artist_tokens = create_token_account(&mut context, &payment_mint, &artist, 0);
+    let prompt_engineer_tokens = create_token_account(&mut context, &payment_mint, &prompt_engineer, 0);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+    let buyer_nft = create_token_account(&mut context, &nft_mint, &buyer.pubkey(), 0);
+
+    // The seller minted the piece and splits royalties 70/30 with a collaborator
+    let creators = vec![
+        CreatorShare { address: artist, share_bps: 7_000 },
+        CreatorShare { address: prompt_engineer, share_bps: 3_000 },
+    ];
+    process(
+        &mut context,
+        &[
+            register_royalties_instruction(&program_id, &seller.pubkey(), &nft_mint, 10, creators),
+            list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, 10_000, 10),
+        ],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+
+    let purchase = |price| {
+        purchase_artwork_instruction(
+            &program_id,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &nft_mint,
+            &proceeds,
+            &buyer_tokens,
+            &buyer_nft,
+            &[artist_tokens, prompt_engineer_tokens],
+            &fees,
+            price,
+        )
+    };
+
+    // A price that no longer matches the listing is refused
+    let err = process(&mut context, &[purchase(9_000)], &[&buyer]).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::PriceMismatch as u32))
+    );
+
+    // Paying fees or royalties to an arbitrary account is refused
+    for index in [7, 11] {
+        let mut instruction = purchase(10_000);
+        instruction.accounts[index] = AccountMeta::new(buyer_tokens, false);
+        let err = process(&mut context, &[instruction], &[&buyer]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(VortexError::InvalidAccount as u32))
+        );
+    }
+
+    process(&mut context, &[purchase(10_000)], &[&buyer]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_tokens).await, 90_000);
+    assert_eq!(token_balance(&mut context, &artist_tokens).await, 700);
+    assert_eq!(token_balance(&mut context, &prompt_engineer_tokens).await, 300);
+    assert_eq!(token_balance(&mut context, &fees).await, 250);
+    assert_eq!(token_balance(&mut context, &proceeds).await, 8_750);
+    assert_eq!(token_balance(&mut context, &buyer_nft).await, 1);
+    assert!(!listing(&mut context, &listing_key).await.is_active);
+
+    // The listing cannot be bought twice
+    let err = process(&mut context, &[purchase(10_000)], &[&buyer]).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::InvalidState as u32))
+    );
+}
+
+#[tokio::test]
+async fn test_config_bounds_listings() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let payment_mint = create_payment_mint(&mut context);
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+
+    // Only the upgrade authority may create the config
+    let admin = context.payer.pubkey();
+    set_upgrade_authority(&mut context, &program_id, &admin);
+    let initialize = initialize_config_instruction(&program_id, &seller.pubkey(), &proceeds, vec![payment_mint]);
+    let err = process(&mut context, &[initialize], &[&seller]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::Unauthorized);
+    initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+
+    let list = |royalty_percentage, proceeds: &Pubkey| {
+        list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, proceeds, 500, royalty_percentage)
+    };
+
+    // Royalties above the configured maximum are refused
+    let err = process(&mut context, &[list(51, &proceeds)], &[&seller]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::RoyaltyTooHigh);
+
+    // So are payment mints outside the allowlist
+    let other_mint = create_payment_mint(&mut context);
+    let other_proceeds = create_token_account(&mut context, &other_mint, &seller.pubkey(), 0);
+    let err = process(&mut context, &[list(0, &other_proceeds)], &[&seller]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::PaymentMintNotAccepted);
+
+    // Only the admin may update the config
+    let mut updated = config(&mut context, &program_id).await;
+    updated.paused = true;
+    updated.accepted_mints.push(other_mint);
+    let err = process(&mut context, &[update_config_instruction(&program_id, &seller.pubkey(), &updated)], &[&seller])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+
+    process(&mut context, &[update_config_instruction(&program_id, &admin, &updated)], &[])
+        .await
+        .unwrap();
+    assert_eq!(config(&mut context, &program_id).await.accepted_mints, vec![payment_mint, other_mint]);
+
+    // Listing is closed while paused
+    let err = process(&mut context, &[list(0, &other_proceeds)], &[&seller]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::MarketplacePaused);
+
+    updated.paused = false;
+    process(&mut context, &[update_config_instruction(&program_id, &admin, &updated)], &[])
+        .await
+        .unwrap();
+    process(&mut context, &[list(0, &other_proceeds)], &[&seller]).await.unwrap();
+
+    // A fee plus maximum royalty above 100% is rejected
+    updated.max_royalty_percentage = 99;
+    assert!(process(&mut context, &[update_config_instruction(&program_id, &admin, &updated)], &[])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_register_royalties_validation() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let minter = Keypair::new();
+    fund_lamports(&mut context, &minter.pubkey()).await;
+    let (nft_mint, _) = create_nft(&mut context, &minter.pubkey());
+    let share = |share_bps| CreatorShare { address: Pubkey::new_unique(), share_bps };
+
+    // Shares must cover exactly 100%
+    assert!(process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &minter.pubkey(), &nft_mint, 10, vec![share(5_000), share(4_000)])],
+        &[&minter],
+    )
+    .await
+    .is_err());
+
+    // Only the minter may register
+    let stranger = Keypair::new();
+    fund_lamports(&mut context, &stranger.pubkey()).await;
+    let err = process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &stranger.pubkey(), &nft_mint, 10, vec![share(10_000)])],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::Unauthorized as u32))
+    );
+
+    process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &minter.pubkey(), &nft_mint, 10, vec![share(10_000)])],
+        &[&minter],
+    )
+    .await
+    .unwrap();
+    let account = context
+        .banks_client
+        .get_account(royalty_address(&program_id, &nft_mint))
+        .await
+        .unwrap()
+        .unwrap();
+    let royalties = RoyaltyConfig::try_from_slice(&account.data).unwrap();
+    assert_eq!(royalties.royalty_percentage, 10);
+    assert_eq!(royalties.split(999), vec![999]);
+
+    // The split cannot be rewritten later
+    assert!(process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &minter.pubkey(), &nft_mint, 5, vec![share(10_000)])],
+        &[&minter],
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn test_english_auction() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    fund_lamports(&mut context, &alice.pubkey()).await;
+    fund_lamports(&mut context, &bob.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+    let alice_tokens = create_token_account(&mut context, &payment_mint, &alice.pubkey(), 10_000);
+    let bob_tokens = create_token_account(&mut context, &payment_mint, &bob.pubkey(), 10_000);
+    let alice_nft = create_token_account(&mut context, &nft_mint, &alice.pubkey(), 0);
+    let (auction, nft_escrow, bid_escrow) = auction_address(&program_id, &seller.pubkey(), &nft_mint);
+
+    let start = clock(&mut context).await.unix_timestamp;
+    set_clock(&mut context, start).await;
+    let end = start + 3_600;
+    process(
+        &mut context,
+        &[create_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &payment_mint, &proceeds, 1_000, start, end)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &nft_escrow).await, 1);
+
+    // Below the reserve
+    let err = process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &alice.pubkey(), &alice_tokens, &alice.pubkey(), 900)],
+        &[&alice],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::BidTooLow);
+
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &alice.pubkey(), &alice_tokens, &alice.pubkey(), 1_000)],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+
+    // Bob must beat Alice by 5%
+    let err = process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &bob.pubkey(), &bob_tokens, &alice.pubkey(), 1_040)],
+        &[&bob],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::BidTooLow);
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &bob.pubkey(), &bob_tokens, &alice.pubkey(), 1_050)],
+        &[&bob],
+    )
+    .await
+    .unwrap();
+    // Alice's bid waits in escrow until she withdraws it
+    assert_eq!(token_balance(&mut context, &alice_tokens).await, 9_000);
+    assert_eq!(token_balance(&mut context, &bid_escrow).await, 2_050);
+    let withdraw = |bidder: &Keypair, destination: &Pubkey| {
+        withdraw_bid_refund_instruction(&program_id, &auction, &bidder.pubkey(), destination)
+    };
+    process(&mut context, &[withdraw(&alice, &alice_tokens)], &[&alice]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &alice_tokens).await, 10_000);
+    assert_eq!(token_balance(&mut context, &bid_escrow).await, 1_050);
+    let err = process(&mut context, &[withdraw(&alice, &alice_tokens)], &[&alice]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    // A bid in the last minute extends the auction
+    set_clock(&mut context, end - 60).await;
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &alice.pubkey(), &alice_tokens, &bob.pubkey(), 1_200)],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &bob_tokens).await, 8_950);
+    process(&mut context, &[withdraw(&bob, &bob_tokens)], &[&bob]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &bob_tokens).await, 10_000);
+
+    set_clock(&mut context, end).await;
+    let settle = || settle_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &alice_nft, &proceeds, &fees);
+    let err = process(&mut context, &[settle()], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::AuctionInProgress);
+
+    set_clock(&mut context, end - 60 + ANTI_SNIPING_WINDOW).await;
+    process(&mut context, &[settle()], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &alice_nft).await, 1);
+    assert_eq!(token_balance(&mut context, &alice_tokens).await, 8_800);
+    assert_eq!(token_balance(&mut context, &fees).await, 30);
+    assert_eq!(token_balance(&mut context, &proceeds).await, 1_170);
+    assert_eq!(token_balance(&mut context, &bid_escrow).await, 0);
+}
+
+#[tokio::test]
+async fn test_frozen_leader_cannot_block_bids() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    fund_lamports(&mut context, &alice.pubkey()).await;
+    fund_lamports(&mut context, &bob.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let payment_mint = create_payment_mint(&mut context);
+    initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let alice_tokens = create_token_account(&mut context, &payment_mint, &alice.pubkey(), 10_000);
+    let bob_tokens = create_token_account(&mut context, &payment_mint, &bob.pubkey(), 10_000);
+    let (auction, _, bid_escrow) = auction_address(&program_id, &seller.pubkey(), &nft_mint);
+
+    let now = clock(&mut context).await.unix_timestamp;
+    process(
+        &mut context,
+        &[create_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &payment_mint, &proceeds, 1_000, now, now + 3_600)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &alice.pubkey(), &alice_tokens, &alice.pubkey(), 1_000)],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+
+    // The leader freezes the account their bid came from
+    set_packed_account(
+        &mut context,
+        &alice_tokens,
+        spl_token::state::Account {
+            mint: payment_mint,
+            owner: alice.pubkey(),
+            amount: 9_000,
+            state: spl_token::state::AccountState::Frozen,
+            ..Default::default()
+        },
+    );
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &bob.pubkey(), &bob_tokens, &alice.pubkey(), 1_100)],
+        &[&bob],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &bid_escrow).await, 2_100);
+
+    // Alice can still take her refund elsewhere
+    let fresh = create_token_account(&mut context, &payment_mint, &alice.pubkey(), 0);
+    process(
+        &mut context,
+        &[withdraw_bid_refund_instruction(&program_id, &auction, &alice.pubkey(), &fresh)],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &fresh).await, 1_000);
+    assert_eq!(token_balance(&mut context, &bid_escrow).await, 1_100);
+
+    // The leader has nothing to withdraw
+    let err = process(
+        &mut context,
+        &[withdraw_bid_refund_instruction(&program_id, &auction, &bob.pubkey(), &bob_tokens)],
+        &[&bob],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::NothingToClaim);
+}
+
+#[tokio::test]
+async fn test_leader_reclaims_bid_when_settlement_is_blocked() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let alice = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    fund_lamports(&mut context, &alice.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+    let alice_tokens = create_token_account(&mut context, &payment_mint, &alice.pubkey(), 10_000);
+    let alice_nft = create_token_account(&mut context, &nft_mint, &alice.pubkey(), 0);
+    let (auction, _, bid_escrow) = auction_address(&program_id, &seller.pubkey(), &nft_mint);
+
+    let start = clock(&mut context).await.unix_timestamp;
+    let end = start + 3_600;
+    process(
+        &mut context,
+        &[create_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &payment_mint, &proceeds, 1_000, start, end)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &alice.pubkey(), &alice_tokens, &alice.pubkey(), 1_000)],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+
+    // The seller freezes their proceeds account, so settling keeps failing
+    set_packed_account(
+        &mut context,
+        &proceeds,
+        spl_token::state::Account {
+            mint: payment_mint,
+            owner: seller.pubkey(),
+            state: spl_token::state::AccountState::Frozen,
+            ..Default::default()
+        },
+    );
+    set_clock(&mut context, end).await;
+    let settle = |destination: &Pubkey| {
+        settle_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, destination, &proceeds, &fees)
+    };
+    assert!(process(&mut context, &[settle(&alice_nft)], &[]).await.is_err());
+
+    // The leader's bid stays committed through the grace period
+    let withdraw = withdraw_bid_refund_instruction(&program_id, &auction, &alice.pubkey(), &alice_tokens);
+    set_clock(&mut context, end + SETTLEMENT_GRACE_PERIOD).await;
+    let err = process(&mut context, std::slice::from_ref(&withdraw), &[&alice]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::NothingToClaim);
+
+    // After it, the leader takes the bid back and the NFT can only go home
+    set_clock(&mut context, end + SETTLEMENT_GRACE_PERIOD + 1).await;
+    process(&mut context, &[withdraw], &[&alice]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &alice_tokens).await, 10_000);
+    assert_eq!(token_balance(&mut context, &bid_escrow).await, 0);
+    let record = bid_record_address(&program_id, &auction, &alice.pubkey());
+    assert!(context.banks_client.get_account(record).await.unwrap().is_none());
+    assert!(process(&mut context, &[settle(&alice_nft)], &[]).await.is_err());
+    process(&mut context, &[settle(&seller_nft)], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &seller_nft).await, 1);
+    assert_eq!(token_balance(&mut context, &alice_nft).await, 0);
+}
+
+#[tokio::test]
+async fn test_cancel_auction_without_bids() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let bidder = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    fund_lamports(&mut context, &bidder.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let payment_mint = create_payment_mint(&mut context);
+    initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let bidder_tokens = create_token_account(&mut context, &payment_mint, &bidder.pubkey(), 10_000);
+    let (auction, _, _) = auction_address(&program_id, &seller.pubkey(), &nft_mint);
+
+    let now = clock(&mut context).await.unix_timestamp;
+    let create = || create_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &payment_mint, &proceeds, 1_000, now, now + 3_600);
+    let cancel = || cancel_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft);
+    process(&mut context, &[create()], &[&seller]).await.unwrap();
+    process(&mut context, &[cancel()], &[&seller]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &seller_nft).await, 1);
+
+    // Once someone has bid, the seller is committed
+    process(&mut context, &[create()], &[&seller]).await.unwrap();
+    process(
+        &mut context,
+        &[place_bid_instruction(&program_id, &auction, &bidder.pubkey(), &bidder_tokens, &bidder.pubkey(), 1_000)],
+        &[&bidder],
+    )
+    .await
+    .unwrap();
+    let err = process(&mut context, &[cancel()], &[&seller]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidState);
+}
+
+#[test]
+fn test_dutch_auction_price_decay() {
+    let linear = DutchAuctionTerms {
+        start_price: 10_000,
+        floor_price: 2_000,
+        start_time: 1_000,
+        end_time: 1_800,
+        step_duration: 0,
+    };
+    assert_eq!(linear.price_at(0).unwrap(), 10_000);
+    assert_eq!(linear.price_at(1_100).unwrap(), 9_000);
+    assert_eq!(linear.price_at(1_400).unwrap(), 6_000);
+    assert_eq!(linear.price_at(1_800).unwrap(), 2_000);
+    assert_eq!(linear.price_at(9_999).unwrap(), 2_000);
+
+    // Stepwise holds each price for 200 seconds
+    let stepwise = DutchAuctionTerms { step_duration: 200, ..linear };
+    assert_eq!(stepwise.price_at(1_199).unwrap(), 10_000);
+    assert_eq!(stepwise.price_at(1_200).unwrap(), 8_000);
+    assert_eq!(stepwise.price_at(1_799).unwrap(), 4_000);
+    assert_eq!(stepwise.price_at(1_800).unwrap(), 2_000);
+
+    assert!(DutchAuctionTerms { floor_price: 10_000, ..linear }.validate().is_err());
+    assert!(DutchAuctionTerms { step_duration: 801, ..linear }.validate().is_err());
+    assert!(stepwise.validate().is_ok());
+
+    // Spans too wide for an i64 are rejected rather than wrapping
+    let extreme = DutchAuctionTerms { start_time: i64::MIN, end_time: i64::MAX, ..linear };
+    assert_eq!(extreme.validate(), Err(VortexError::MathOverflow.into()));
+    assert_eq!(extreme.price_at(0), Err(VortexError::MathOverflow.into()));
+}
+
+#[tokio::test]
+async fn test_dutch_auction_purchase_pays_current_price() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let buyer = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+    let buyer_tokens = create_token_account(&mut context, &payment_mint, &buyer.pubkey(), 100_000);
+    let buyer_nft = create_token_account(&mut context, &nft_mint, &buyer.pubkey(), 0);
+
+    let start = clock(&mut context).await.unix_timestamp + 100;
+    let terms = DutchAuctionTerms {
+        start_price: 10_000,
+        floor_price: 2_000,
+        start_time: start,
+        end_time: start + 800,
+        step_duration: 0,
+    };
+    process(
+        &mut context,
+        &[list_dutch_auction_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, terms)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+
+    let purchase = |max_price| {
+        purchase_artwork_instruction(
+            &program_id,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &nft_mint,
+            &proceeds,
+            &buyer_tokens,
+            &buyer_nft,
+            &[],
+            &fees,
+            max_price,
+        )
+    };
+
+    // Not on sale before the window opens
+    let err = process(&mut context, &[purchase(10_000)], &[&buyer]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::AuctionClosed);
+
+    // Halfway through the price is 6000; a lower cap is refused
+    set_clock(&mut context, start + 400).await;
+    let err = process(&mut context, &[purchase(5_000)], &[&buyer]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::PriceMismatch);
+
+    process(&mut context, &[purchase(7_000)], &[&buyer]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_tokens).await, 94_000);
+    assert_eq!(token_balance(&mut context, &fees).await, 150);
+    assert_eq!(token_balance(&mut context, &proceeds).await, 5_850);
+    assert_eq!(token_balance(&mut context, &buyer_nft).await, 1);
+}
+
+#[tokio::test]
+async fn test_offer_accept_and_cancel() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let holder = Keypair::new();
+    let buyer = Keypair::new();
+    let rival = Keypair::new();
+    let artist = Keypair::new();
+    fund_lamports(&mut context, &buyer.pubkey()).await;
+    fund_lamports(&mut context, &rival.pubkey()).await;
+    fund_lamports(&mut context, &artist.pubkey()).await;
+    let (nft_mint, holder_nft) = create_nft(&mut context, &artist.pubkey());
+
+    // The artist minted the piece and it has since changed hands
+    set_packed_account(
+        &mut context,
+        &holder_nft,
+        spl_token::state::Account {
+            mint: nft_mint,
+            owner: holder.pubkey(),
+            amount: 1,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        },
+    );
+
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &holder.pubkey(), 0);
+    let buyer_tokens = create_token_account(&mut context, &payment_mint, &buyer.pubkey(), 10_000);
+    let rival_tokens = create_token_account(&mut context, &payment_mint, &rival.pubkey(), 10_000);
+    let artist_tokens = create_token_account(&mut context, &payment_mint, &artist.pubkey(), 0);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+    let buyer_nft = create_token_account(&mut context, &nft_mint, &buyer.pubkey(), 0);
+    let rival_nft = create_token_account(&mut context, &nft_mint, &rival.pubkey(), 0);
+    let creators = vec![CreatorShare { address: artist.pubkey(), share_bps: 10_000 }];
+    process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &artist.pubkey(), &nft_mint, 10, creators)],
+        &[&artist],
+    )
+    .await
+    .unwrap();
+
+    let now = clock(&mut context).await.unix_timestamp;
+    let make = |buyer: &Pubkey, tokens: &Pubkey, amount, expiry| {
+        make_offer_instruction(&program_id, buyer, &nft_mint, tokens, &payment_mint, amount, expiry)
+    };
+
+    // Offers must be live when made
+    let err = process(&mut context, &[make(&buyer.pubkey(), &buyer_tokens, 4_000, now)], &[&buyer])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::InvalidInstruction);
+
+    process(&mut context, &[make(&buyer.pubkey(), &buyer_tokens, 4_000, now + 3_600)], &[&buyer])
+        .await
+        .unwrap();
+    process(&mut context, &[make(&rival.pubkey(), &rival_tokens, 3_000, now + 60)], &[&rival])
+        .await
+        .unwrap();
+    let (_, buyer_escrow) = offer_address(&program_id, &buyer.pubkey(), &nft_mint);
+    assert_eq!(token_balance(&mut context, &buyer_tokens).await, 6_000);
+    assert_eq!(token_balance(&mut context, &buyer_escrow).await, 4_000);
+
+    // A second live offer from the same buyer is refused
+    let err = process(&mut context, &[make(&buyer.pubkey(), &buyer_tokens, 5_000, now + 3_600)], &[&buyer])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::AlreadyInitialized);
+
+    // Only the buyer can withdraw an offer
+    let mut cancel = cancel_offer_instruction(&program_id, &rival.pubkey(), &nft_mint, &rival_tokens);
+    cancel.accounts[0] = AccountMeta::new_readonly(holder.pubkey(), true);
+    let err = process(&mut context, &[cancel], &[&holder]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::Unauthorized);
+
+    let accept = |buyer: &Pubkey, buyer_nft: &Pubkey| {
+        accept_offer_instruction(
+            &program_id,
+            &holder.pubkey(),
+            buyer,
+            &nft_mint,
+            &holder_nft,
+            buyer_nft,
+            &proceeds,
+            &[artist_tokens],
+            &fees,
+        )
+    };
+
+    // The rival's offer lapses before the holder gets to it
+    set_clock(&mut context, now + 60).await;
+    let err = process(&mut context, &[accept(&rival.pubkey(), &rival_nft)], &[&holder])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::OfferExpired);
+    process(
+        &mut context,
+        &[cancel_offer_instruction(&program_id, &rival.pubkey(), &nft_mint, &rival_tokens)],
+        &[&rival],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &rival_tokens).await, 10_000);
+
+    // The NFT must go to the buyer who made the offer
+    let err = process(&mut context, &[accept(&buyer.pubkey(), &rival_nft)], &[&holder])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    process(&mut context, &[accept(&buyer.pubkey(), &buyer_nft)], &[&holder])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_nft).await, 1);
+    assert_eq!(token_balance(&mut context, &holder_nft).await, 0);
+    assert_eq!(token_balance(&mut context, &buyer_escrow).await, 0);
+    assert_eq!(token_balance(&mut context, &fees).await, 100);
+    assert_eq!(token_balance(&mut context, &artist_tokens).await, 400);
+    assert_eq!(token_balance(&mut context, &proceeds).await, 3_500);
+
+    // An accepted offer can neither be accepted again nor refunded
+    let err = process(
+        &mut context,
+        &[cancel_offer_instruction(&program_id, &buyer.pubkey(), &nft_mint, &buyer_tokens)],
+        &[&buyer],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::InvalidState);
+}
+
+#[tokio::test]
+async fn test_collection_offer() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let authority = Keypair::new();
+    let collection_key = Keypair::new();
+    let holder = Keypair::new();
+    let buyer = Keypair::new();
+    fund_lamports(&mut context, &authority.pubkey()).await;
+    fund_lamports(&mut context, &buyer.pubkey()).await;
+    let collection = collection_address(&program_id, &collection_key.pubkey());
+    let (member_mint, member_nft) = create_nft(&mut context, &holder.pubkey());
+    let (stray_mint, stray_nft) = create_nft(&mut context, &holder.pubkey());
+    process(
+        &mut context,
+        &[create_collection_instruction(&program_id, &authority.pubkey(), &collection_key.pubkey())],
+        &[&authority, &collection_key],
+    )
+    .await
+    .unwrap();
+
+    // An NFT whose holder could still mint copies of it cannot join
+    let verify =
+        |mint: &Pubkey| verify_collection_member_instruction(&program_id, &authority.pubkey(), &collection, mint);
+    let err = process(&mut context, &[verify(&member_mint)], &[&authority]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+    revoke_mint_authority(&mut context, &member_mint);
+    revoke_mint_authority(&mut context, &stray_mint);
+    process(&mut context, &[verify(&member_mint)], &[&authority]).await.unwrap();
+
+    // Only the collection authority can vouch for members
+    let err = process(
+        &mut context,
+        &[verify_collection_member_instruction(&program_id, &buyer.pubkey(), &collection, &stray_mint)],
+        &[&buyer],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &holder.pubkey(), 0);
+    let buyer_tokens = create_token_account(&mut context, &payment_mint, &buyer.pubkey(), 10_000);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+    let buyer_member_nft = create_token_account(&mut context, &member_mint, &buyer.pubkey(), 0);
+    let buyer_stray_nft = create_token_account(&mut context, &stray_mint, &buyer.pubkey(), 0);
+
+    let now = clock(&mut context).await.unix_timestamp;
+    process(
+        &mut context,
+        &[make_collection_offer_instruction(
+            &program_id,
+            &buyer.pubkey(),
+            &collection,
+            &buyer_tokens,
+            &payment_mint,
+            4_000,
+            now + 3_600,
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    let accept = |nft_mint: &Pubkey, holder_nft: &Pubkey, buyer_nft: &Pubkey| {
+        accept_collection_offer_instruction(
+            &program_id,
+            &holder.pubkey(),
+            &buyer.pubkey(),
+            &collection,
+            nft_mint,
+            holder_nft,
+            buyer_nft,
+            &proceeds,
+            &fees,
+        )
+    };
+
+    // NFTs outside the collection cannot fill the offer
+    let err = process(&mut context, &[accept(&stray_mint, &stray_nft, &buyer_stray_nft)], &[&holder])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    // Nor can a collection offer be filled as a single-NFT offer
+    let mut instruction = accept(&member_mint, &member_nft, &buyer_member_nft);
+    instruction.data = MarketplaceInstruction::AcceptOffer {}.try_to_vec().unwrap();
+    instruction.accounts.remove(6);
+    let err = process(&mut context, &[instruction], &[&holder]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    process(&mut context, &[accept(&member_mint, &member_nft, &buyer_member_nft)], &[&holder])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_member_nft).await, 1);
+    assert_eq!(token_balance(&mut context, &fees).await, 100);
+    assert_eq!(token_balance(&mut context, &proceeds).await, 3_900);
+}
+
+#[tokio::test]
+async fn test_sweep_floor_buys_cheapest_listings() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let authority = Keypair::new();
+    let collection_key = Keypair::new();
+    let seller = Keypair::new();
+    let buyer = Keypair::new();
+    fund_lamports(&mut context, &authority.pubkey()).await;
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    let collection = collection_address(&program_id, &collection_key.pubkey());
+
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let buyer_tokens = create_token_account(&mut context, &payment_mint, &buyer.pubkey(), 10_000);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+
+    process(
+        &mut context,
+        &[create_collection_instruction(&program_id, &authority.pubkey(), &collection_key.pubkey())],
+        &[&authority, &collection_key],
+    )
+    .await
+    .unwrap();
+
+    let mut candidates = Vec::new();
+    for price in [3_000, 1_000, 2_000, 500] {
+        let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+        revoke_mint_authority(&mut context, &nft_mint);
+        let buyer_nft = create_token_account(&mut context, &nft_mint, &buyer.pubkey(), 0);
+        process(
+            &mut context,
+            &[
+                verify_collection_member_instruction(&program_id, &authority.pubkey(), &collection, &nft_mint),
+                list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, price, 0),
+            ],
+            &[&authority, &seller],
+        )
+        .await
+        .unwrap();
+        candidates.push((seller.pubkey(), nft_mint, proceeds, buyer_nft));
+    }
+
+    // The cheapest piece is delisted before the sweep lands and is skipped
+    let (_, cheapest_mint, _, cheapest_buyer_nft) = candidates[3];
+    let seller_cheapest_nft = create_token_account(&mut context, &cheapest_mint, &seller.pubkey(), 0);
+    process(
+        &mut context,
+        &[cancel_listing_instruction(&program_id, &seller.pubkey(), &cheapest_mint, &seller_cheapest_nft)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+
+    // A listing outside the collection spoils the whole sweep
+    let (stray_mint, stray_nft) = create_nft(&mut context, &seller.pubkey());
+    let stray_buyer_nft = create_token_account(&mut context, &stray_mint, &buyer.pubkey(), 0);
+    process(
+        &mut context,
+        &[list_artwork_instruction(&program_id, &seller.pubkey(), &stray_mint, &stray_nft, &proceeds, 100, 0)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    let mut with_stray = candidates.clone();
+    with_stray.push((seller.pubkey(), stray_mint, proceeds, stray_buyer_nft));
+    let sweep = |candidates: &[(Pubkey, Pubkey, Pubkey, Pubkey)], max_count, max_total| {
+        sweep_floor_instruction(
+            &program_id,
+            &buyer.pubkey(),
+            &buyer_tokens,
+            &collection,
+            &fees,
+            candidates,
+            max_count,
+            max_total,
+        )
+    };
+    let err = process(&mut context, &[sweep(&with_stray, 3, 10_000)], &[&buyer])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    // Buys the 1,000 and 2,000 pieces; the 3,000 one would break the budget
+    process(&mut context, &[sweep(&candidates, 3, 5_000)], &[&buyer])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_tokens).await, 7_000);
+    assert_eq!(token_balance(&mut context, &fees).await, 75);
+    assert_eq!(token_balance(&mut context, &proceeds).await, 2_925);
+    assert_eq!(token_balance(&mut context, &candidates[1].3).await, 1);
+    assert_eq!(token_balance(&mut context, &candidates[2].3).await, 1);
+    assert_eq!(token_balance(&mut context, &candidates[0].3).await, 0);
+    assert_eq!(token_balance(&mut context, &cheapest_buyer_nft).await, 0);
+    let (first_listing, _) = listing_address(&program_id, &seller.pubkey(), &candidates[0].1);
+    assert!(listing(&mut context, &first_listing).await.is_active);
+
+    // The count cap stops the sweep even with budget to spare
+    process(&mut context, &[sweep(&candidates, 0, 10_000)], &[&buyer])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_tokens).await, 7_000);
+
+    // A listing closed out once expired is skipped too
+    let now = clock(&mut context).await.unix_timestamp;
+    let (expired_mint, expired_nft) = create_nft(&mut context, &seller.pubkey());
+    revoke_mint_authority(&mut context, &expired_mint);
+    let expired_buyer_nft = create_token_account(&mut context, &expired_mint, &buyer.pubkey(), 0);
+    process(
+        &mut context,
+        &[
+            verify_collection_member_instruction(&program_id, &authority.pubkey(), &collection, &expired_mint),
+            list_artwork_instruction(&program_id, &seller.pubkey(), &expired_mint, &expired_nft, &proceeds, 100, 0),
+            update_listing_instruction(&program_id, &seller.pubkey(), &expired_mint, 100, now + 100),
+        ],
+        &[&authority, &seller],
+    )
+    .await
+    .unwrap();
+    set_clock(&mut context, now + 100).await;
+    process(
+        &mut context,
+        &[close_expired_listing_instruction(&program_id, &seller.pubkey(), &expired_mint, &expired_nft)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let mut with_closed = candidates.clone();
+    with_closed.push((seller.pubkey(), expired_mint, proceeds, expired_buyer_nft));
+    process(&mut context, &[sweep(&with_closed, 1, 10_000)], &[&buyer])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context, &buyer_tokens).await, 4_000);
+    assert_eq!(token_balance(&mut context, &candidates[0].3).await, 1);
+    assert_eq!(token_balance(&mut context, &expired_buyer_nft).await, 0);
+}
+
+#[tokio::test]
+async fn test_update_and_expire_listing() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let buyer = Keypair::new();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let (listing_key, escrow) = listing_address(&program_id, &seller.pubkey(), &nft_mint);
+    let payment_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![payment_mint]).await;
+    let proceeds = create_token_account(&mut context, &payment_mint, &seller.pubkey(), 0);
+    let buyer_tokens = create_token_account(&mut context, &payment_mint, &buyer.pubkey(), 10_000);
+    let buyer_nft = create_token_account(&mut context, &nft_mint, &buyer.pubkey(), 0);
+    let fees = create_token_account(&mut context, &payment_mint, &fee_recipient, 0);
+
+    process(
+        &mut context,
+        &[list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, 500, 0)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+
+    // Only the seller can reprice, and the expiry must be in the future
+    let now = clock(&mut context).await.unix_timestamp;
+    let mut instruction = update_listing_instruction(&program_id, &seller.pubkey(), &nft_mint, 1, 0);
+    instruction.accounts[0] = AccountMeta::new_readonly(buyer.pubkey(), true);
+    let err = process(&mut context, &[instruction], &[&buyer]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::Unauthorized);
+    let err = process(
+        &mut context,
+        &[update_listing_instruction(&program_id, &seller.pubkey(), &nft_mint, 800, now)],
+        &[&seller],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::InvalidInstruction);
+
+    process(
+        &mut context,
+        &[update_listing_instruction(&program_id, &seller.pubkey(), &nft_mint, 800, now + 100)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    let updated = listing(&mut context, &listing_key).await;
+    assert_eq!((updated.price, updated.expires_at), (800, now + 100));
+    assert_eq!(token_balance(&mut context, &escrow).await, 1);
+
+    // A live listing cannot be closed out from under the seller
+    let close =
+        |seller_nft: &Pubkey| close_expired_listing_instruction(&program_id, &seller.pubkey(), &nft_mint, seller_nft);
+    let err = process(&mut context, &[close(&seller_nft)], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidState);
+
+    set_clock(&mut context, now + 100).await;
+    let purchase = purchase_artwork_instruction(
+        &program_id,
+        &buyer.pubkey(),
+        &seller.pubkey(),
+        &nft_mint,
+        &proceeds,
+        &buyer_tokens,
+        &buyer_nft,
+        &[],
+        &fees,
+        800,
+    );
+    let err = process(&mut context, &[purchase], &[&buyer]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::ListingExpired);
+
+    // The NFT can only be returned to the seller
+    let err = process(&mut context, &[close(&buyer_nft)], &[]).await.unwrap_err().unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    let rent = context.banks_client.get_account(listing_key).await.unwrap().unwrap().lamports
+        + context.banks_client.get_account(escrow).await.unwrap().unwrap().lamports;
+    let seller_lamports = lamports(&mut context, &seller.pubkey()).await;
+    process(&mut context, &[close(&seller_nft)], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &seller_nft).await, 1);
+    assert!(context.banks_client.get_account(listing_key).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(escrow).await.unwrap().is_none());
+    assert_eq!(lamports(&mut context, &seller.pubkey()).await, seller_lamports + rent);
+
+    // Closed accounts are recreated on relisting
+    process(
+        &mut context,
+        &[list_artwork_instruction(&program_id, &seller.pubkey(), &nft_mint, &seller_nft, &proceeds, 900, 0)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    let relisted = listing(&mut context, &listing_key).await;
+    assert_eq!((relisted.price, relisted.expires_at), (900, 0));
+}
+
+#[tokio::test]
+async fn test_purchase_in_sol() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let seller = Keypair::new();
+    let buyer = Keypair::new();
+    let artist = Pubkey::new_unique();
+    fund_lamports(&mut context, &seller.pubkey()).await;
+    fund_lamports(&mut context, &buyer.pubkey()).await;
+    fund_lamports(&mut context, &artist).await;
+    let (nft_mint, seller_nft) = create_nft(&mut context, &seller.pubkey());
+    let buyer_nft = create_token_account(&mut context, &nft_mint, &buyer.pubkey(), 0);
+    let tola_mint = create_payment_mint(&mut context);
+    let fee_recipient = initialize_config(&mut context, &program_id, vec![tola_mint]).await;
+    fund_lamports(&mut context, &fee_recipient).await;
+
+    // SOL is refused until the admin allowlists it
+    let list = list_artwork_instruction(
+        &program_id,
+        &seller.pubkey(),
+        &nft_mint,
+        &seller_nft,
+        &seller.pubkey(),
+        100_000_000,
+        10,
+    );
+    let creators = vec![CreatorShare { address: artist, share_bps: 10_000 }];
+    let err = process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &seller.pubkey(), &nft_mint, 10, creators), list.clone()],
+        &[&seller],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(VortexError::PaymentMintNotAccepted as u32))
+    );
+
+    let mut updated = config(&mut context, &program_id).await;
+    updated.accepted_mints.push(NATIVE_SOL_MINT);
+    let admin = context.payer.pubkey();
+    process(&mut context, &[update_config_instruction(&program_id, &admin, &updated)], &[])
+        .await
+        .unwrap();
+    let creators = vec![CreatorShare { address: artist, share_bps: 10_000 }];
+    process(
+        &mut context,
+        &[register_royalties_instruction(&program_id, &seller.pubkey(), &nft_mint, 10, creators), list],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    let (listing_key, _) = listing_address(&program_id, &seller.pubkey(), &nft_mint);
+    assert_eq!(listing(&mut context, &listing_key).await.payment_mint, NATIVE_SOL_MINT);
+
+    let purchase = |fee_account: &Pubkey| {
+        purchase_artwork_instruction(
+            &program_id,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &nft_mint,
+            &seller.pubkey(),
+            &buyer.pubkey(),
+            &buyer_nft,
+            &[artist],
+            fee_account,
+            100_000_000,
+        )
+    };
+
+    // Fees still go only to the configured recipient
+    let err = process(&mut context, &[purchase(&buyer.pubkey())], &[&buyer])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::InvalidAccount);
+
+    let buyer_before = lamports(&mut context, &buyer.pubkey()).await;
+    let seller_before = lamports(&mut context, &seller.pubkey()).await;
+    let artist_before = lamports(&mut context, &artist).await;
+    let fees_before = lamports(&mut context, &fee_recipient).await;
+    process(&mut context, &[purchase(&fee_recipient)], &[&buyer])
+        .await
+        .unwrap();
+    assert_eq!(buyer_before - lamports(&mut context, &buyer.pubkey()).await, 100_000_000);
+    assert_eq!(lamports(&mut context, &artist).await - artist_before, 10_000_000);
+    assert_eq!(lamports(&mut context, &fee_recipient).await - fees_before, 2_500_000);
+    assert_eq!(lamports(&mut context, &seller.pubkey()).await - seller_before, 87_500_000);
+    assert_eq!(token_balance(&mut context, &buyer_nft).await, 1);
+}
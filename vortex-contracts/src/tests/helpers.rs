@@ -0,0 +1,102 @@
+//! Program-test helpers shared by the token, marketplace and governance tests
+use crate::VortexError;
+use solana_program::{
+    bpf_loader_upgradeable,
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+// Sign with the payer plus `signers` against a fresh blockhash
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+pub fn expect_error(err: TransactionError, expected: VortexError) {
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::Custom(expected as u32)));
+}
+
+// Write an SPL account straight into the bank
+pub fn set_packed_account<T: Pack>(context: &mut ProgramTestContext, address: &Pubkey, state: T) {
+    let mut data = vec![0; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    let mut account = AccountSharedData::new(1_000_000_000, data.len(), &spl_token::id());
+    account.set_data_from_slice(&data);
+    context.set_account(address, &account);
+}
+
+// Write a token account holding `amount` directly into the bank
+pub fn create_token_account(context: &mut ProgramTestContext, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+    let address = Pubkey::new_unique();
+    set_packed_account(
+        context,
+        &address,
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        },
+    );
+    address
+}
+
+// Give a wallet lamports to pay for the accounts it opens
+pub async fn fund_lamports(context: &mut ProgramTestContext, wallet: &Pubkey) {
+    let payer = context.payer.pubkey();
+    process(context, &[system_instruction::transfer(&payer, wallet, 1_000_000_000)], &[])
+        .await
+        .unwrap();
+}
+
+pub async fn token_balance(context: &mut ProgramTestContext, account: &Pubkey) -> u64 {
+    let account = context.banks_client.get_account(*account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+pub async fn clock(context: &mut ProgramTestContext) -> Clock {
+    context.banks_client.get_sysvar::<Clock>().await.unwrap()
+}
+
+pub async fn set_clock(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock = clock(context).await;
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+pub fn program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+// Stand-in for the ProgramData account of a deployed, upgradeable program
+pub fn set_upgrade_authority(context: &mut ProgramTestContext, program_id: &Pubkey, authority: &Pubkey) {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    let mut account = AccountSharedData::new(1_000_000_000, data.len(), &bpf_loader_upgradeable::id());
+    account.set_data_from_slice(&data);
+    context.set_account(&program_data_address(program_id), &account);
+}
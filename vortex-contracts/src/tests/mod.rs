@@ -1,11 +1,16 @@
-mod integration;
+use crate::*;
 
-#[cfg(test)]
-mod unit {
-    mod token;
-    mod marketplace;
-    mod governance;
+pub mod helpers;
+
+#[test]
+fn it_works() {
+    let result = add(2, 2);
+    assert_eq!(result, 4);
 }
 
-// Re-export integration tests
-pub use integration::*; 
\ No newline at end of file
+#[test]
+fn test_error_conversion() {
+    let error = VortexError::InvalidInstruction;
+    let program_error: solana_program::program_error::ProgramError = error.into();
+    assert!(matches!(program_error, solana_program::program_error::ProgramError::Custom(_)));
+}
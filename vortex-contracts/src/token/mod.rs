@@ -1,51 +1,323 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
     entrypoint::ProgramResult,
     msg,
+    keccak,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_pack::Pack,
     pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use spl_token::instruction::TokenInstruction;
+use crate::{
+    events::{emit, VortexEvent},
+    utils::{create_pda_account, create_token_account_pda, resize_account, transfer_tokens, upgrade_authority},
+    VortexError,
+};
+
+#[cfg(test)]
+mod tests;
+
+// Address the TOLA token program is deployed at, which governance binds to.
+// It must match the program keypair used to deploy; see the README.
+solana_program::declare_id!("37eeyVi7bKUMWhJGXjvmUfJYtskJ9SasCo3ZUpaj1DtF");
+
+/// Seed of the `TolaState` PDA, which is also the SPL mint authority
+pub const STATE_SEED: &[u8] = b"tola_state";
+/// Seed of the TOLA mint PDA
+pub const MINT_SEED: &[u8] = b"tola_mint";
+/// Seed of the treasury token account PDA
+pub const TREASURY_SEED: &[u8] = b"tola_treasury";
+/// Seed prefix of `VestingSchedule` PDAs, followed by the beneficiary and
+/// the little-endian schedule id
+pub const VESTING_SEED: &[u8] = b"vesting";
+/// Seed prefix of vesting escrow token accounts, followed by the schedule
+pub const VESTING_ESCROW_SEED: &[u8] = b"vesting_escrow";
+/// Seed of the `StakePool` PDA
+pub const STAKE_POOL_SEED: &[u8] = b"stake_pool";
+/// Seed of the vault holding staked tokens
+pub const STAKE_VAULT_SEED: &[u8] = b"stake_vault";
+/// Seed of the vault rewards are paid from
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
+/// Seed prefix of `StakeAccount` PDAs, followed by the staker
+pub const STAKE_SEED: &[u8] = b"stake";
+/// Longest lock a stake may carry; a stake locked this long votes with its full amount
+pub const MAX_STAKE_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
+/// Fixed-point scale of `StakePool::reward_per_token_stored`
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+/// Decimals of the TOLA mint
+pub const TOLA_DECIMALS: u8 = 9;
+/// Seed of the `RewardSchedule` PDA
+pub const REWARD_SCHEDULE_SEED: &[u8] = b"reward_schedule";
+/// Most epochs a reward schedule may hold
+pub const MAX_REWARD_EPOCHS: usize = 256;
+/// Seed prefix of `MerkleDistributor` PDAs, followed by the little-endian airdrop id
+pub const DISTRIBUTOR_SEED: &[u8] = b"distributor";
+/// Seed prefix of distributor vault token accounts, followed by the distributor
+pub const DISTRIBUTOR_VAULT_SEED: &[u8] = b"distributor_vault";
+/// Seed prefix of claim bitmap accounts, followed by the distributor
+pub const CLAIM_BITMAP_SEED: &[u8] = b"claim_bitmap";
+/// Most leaves a distributor may have; its bitmap must fit in one CPI-created account
+pub const MAX_AIRDROP_LEAVES: u64 = 10_240 * 8;
 
 // Token instruction enum
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum TolaInstruction {
-    /// Initialize the TOLA token
+    /// Initialize the TOLA token; only the program's upgrade authority may
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The account of the person deploying the contract
-    /// 1. `[writable]` The token mint account
+    /// 0. `[signer, writable]` The upgrade authority
+    /// 1. `[writable]` The token mint account (PDA `["tola_mint"]`)
     /// 2. `[]` The rent sysvar
     /// 3. `[]` The token program
+    /// 4. `[writable]` The TOLA state account (PDA `["tola_state"]`)
+    /// 5. `[writable]` The treasury token account (PDA `["tola_treasury"]`)
+    /// 6. `[]` The system program
+    /// 7. `[]` The program's ProgramData account
     Initialize {
         /// Total supply of tokens
         total_supply: u64,
+        /// Hard cap on all tokens ever minted
+        max_supply: u64,
     },
 
-    /// Create vesting schedule
+    /// Create vesting schedule funded from the treasury
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The account creating the vesting schedule
-    /// 1. `[writable]` The vesting account
-    /// 2. `[writable]` The token account to vest
+    /// 0. `[signer, writable]` The admin creating the vesting schedule
+    /// 1. `[writable]` The vesting account (PDA `["vesting", beneficiary, schedule_id]`)
+    /// 2. `[writable]` The treasury token account to vest from
+    /// 3. `[]` The beneficiary
+    /// 4. `[writable]` The vesting escrow token account (PDA `["vesting_escrow", vesting]`)
+    /// 5. `[]` The TOLA state account
+    /// 6. `[]` The token mint account
+    /// 7. `[]` The token program
+    /// 8. `[]` The system program
     CreateVesting {
+        /// Tells apart the schedules of one beneficiary
+        schedule_id: u64,
         amount: u64,
         start_timestamp: i64,
         duration: i64,
+        /// Seconds after the start before anything unlocks
+        cliff_duration: i64,
+        /// Whether the admin may later claw back the unvested part
+        revocable: bool,
     },
 
     /// Enable staking
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The staker
-    /// 1. `[writable]` The stake account
+    /// 0. `[signer, writable]` The staker
+    /// 1. `[writable]` The stake account (PDA `["stake", staker]`)
     /// 2. `[writable]` The token account to stake from
+    /// 3. `[writable]` The stake pool account
+    /// 4. `[writable]` The stake vault token account
+    /// 5. `[]` The token program
+    /// 6. `[]` The system program
+    /// 7. `[]` The TOLA state account
+    /// 8. `[]` The reward schedule account
     Stake {
         amount: u64,
         duration: i64,
     },
+
+    /// Release the vested part of a schedule to its beneficiary
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The beneficiary
+    /// 1. `[writable]` The vesting account
+    /// 2. `[writable]` The vesting escrow token account
+    /// 3. `[writable]` The beneficiary's token account
+    /// 4. `[]` The token program
+    /// 5. `[]` The TOLA state account
+    ClaimVested {},
+
+    /// Return the unvested part of a revocable schedule to the treasury
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The vesting authority
+    /// 1. `[writable]` The vesting account
+    /// 2. `[writable]` The vesting escrow token account
+    /// 3. `[writable]` The treasury token account
+    /// 4. `[]` The TOLA state account
+    /// 5. `[]` The token program
+    RevokeVesting {},
+
+    /// Create the stake pool and its vaults
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin
+    /// 1. `[writable]` The stake pool account (PDA `["stake_pool"]`)
+    /// 2. `[writable]` The stake vault token account (PDA `["stake_vault"]`)
+    /// 3. `[writable]` The reward vault token account (PDA `["reward_vault"]`)
+    /// 4. `[]` The TOLA state account
+    /// 5. `[]` The token mint account
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    /// 8. `[writable]` The reward schedule account (PDA `["reward_schedule"]`)
+    InitializeStakePool {},
+
+    /// Move tokens from the treasury into the reward vault
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin
+    /// 1. `[]` The stake pool account
+    /// 2. `[writable]` The reward vault token account
+    /// 3. `[writable]` The treasury token account
+    /// 4. `[]` The TOLA state account
+    /// 5. `[]` The token program
+    FundRewards {
+        amount: u64,
+    },
+
+    /// Withdraw the whole stake once its lock has expired
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The staker
+    /// 1. `[writable]` The stake account
+    /// 2. `[writable]` The token account to return the stake to
+    /// 3. `[writable]` The stake pool account
+    /// 4. `[writable]` The stake vault token account
+    /// 5. `[]` The token program
+    /// 6. `[]` The TOLA state account
+    /// 7. `[]` The reward schedule account
+    Unstake {},
+
+    /// Pay out accrued staking rewards
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The staker
+    /// 1. `[writable]` The stake account
+    /// 2. `[writable]` The token account to pay rewards to
+    /// 3. `[writable]` The stake pool account
+    /// 4. `[writable]` The reward vault token account
+    /// 5. `[]` The token program
+    /// 6. `[]` The TOLA state account
+    /// 7. `[]` The reward schedule account
+    ClaimRewards {},
+
+    /// Halt staking, vesting and treasury transfers
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin
+    /// 1. `[writable]` The TOLA state account
+    Pause {},
+
+    /// Resume normal operation after a pause
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The admin
+    /// 1. `[writable]` The TOLA state account
+    Unpause {},
+
+    /// Burn tokens from the caller's token account
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The token owner
+    /// 1. `[writable]` The token account to burn from
+    /// 2. `[writable]` The token mint account
+    /// 3. `[writable]` The TOLA state account
+    /// 4. `[]` The token program
+    Burn {
+        amount: u64,
+    },
+
+    /// Hand minting rights to a new authority, or renounce them for good with `None`;
+    /// the default pubkey is refused rather than taken as a silent renouncement
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current mint authority
+    /// 1. `[writable]` The TOLA state account
+    /// 2. `[writable]` The token mint account
+    /// 3. `[]` The token program
+    SetMintAuthority {
+        new_authority: Option<Pubkey>,
+    },
+
+    /// Mint new tokens within the supply cap
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The mint authority
+    /// 1. `[writable]` The token mint account
+    /// 2. `[writable]` The destination token account
+    /// 3. `[writable]` The TOLA state account
+    /// 4. `[]` The token program
+    MintTo {
+        amount: u64,
+    },
+
+    /// Publish a Merkle root for an airdrop and fund its vault from the treasury
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin
+    /// 1. `[writable]` The distributor account (PDA `["distributor", airdrop_id]`)
+    /// 2. `[writable]` The distributor vault (PDA `["distributor_vault", distributor]`)
+    /// 3. `[writable]` The claim bitmap account (PDA `["claim_bitmap", distributor]`)
+    /// 4. `[writable]` The treasury token account
+    /// 5. `[]` The TOLA state account
+    /// 6. `[]` The token mint account
+    /// 7. `[]` The token program
+    /// 8. `[]` The system program
+    CreateDistributor {
+        airdrop_id: u64,
+        merkle_root: [u8; 32],
+        /// Sum of all leaf amounts, moved into the vault up front
+        total_amount: u64,
+        /// Number of leaves in the tree; sizes the claim bitmap
+        num_leaves: u64,
+    },
+
+    /// Claim an airdrop allocation by proving `(index, claimant, amount)` is in the tree
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The claimant
+    /// 1. `[writable]` The distributor account
+    /// 2. `[writable]` The claim bitmap account
+    /// 3. `[writable]` The distributor vault
+    /// 4. `[writable]` The claimant's token account
+    /// 5. `[]` The token program
+    /// 6. `[]` The TOLA state account
+    ClaimAirdrop {
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    },
+
+    /// Extend the reward schedule with `count` epochs, each emitting
+    /// `step_down_bps` less per second than the one before (5000 halves)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin, paying for the larger account
+    /// 1. `[writable]` The reward schedule account
+    /// 2. `[]` The TOLA state account
+    /// 3. `[]` The system program
+    AppendRewardEpochs {
+        count: u16,
+        epoch_duration: i64,
+        /// Emission per second of the first appended epoch
+        initial_rate: u64,
+        step_down_bps: u16,
+    },
+
+    /// Refresh the burned and circulating supply from the mint, picking up
+    /// burns made directly through the token program
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The TOLA state account
+    /// 1. `[]` The token mint account
+    SyncSupply {},
+
+    /// Hand the admin role to a new authority, such as the governance
+    /// program's authority PDA
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The current admin
+    /// 1. `[writable]` The TOLA state account
+    SetAdmin {
+        new_admin: Pubkey,
+    },
 }
 
 // Program state
@@ -54,10 +326,315 @@ pub struct TolaState {
     pub total_supply: u64,
     pub mint_authority: Pubkey,
     pub initialized: bool,
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub bump: u8,
+    /// Authority over treasury allocations and the pause switch
+    pub admin: Pubkey,
+    pub paused: bool,
+    /// Everything minted that the mint no longer holds, however it was burned.
+    /// Like `circulating_supply`, it is as of the last `sync_supply`; the
+    /// mint's own `supply` is the live figure.
+    pub total_burned: u64,
+    /// Minted supply that has not been burned
+    pub circulating_supply: u64,
+    /// Cap on `total_supply`, fixed at initialization
+    pub max_supply: u64,
+    /// Set once minting has been renounced; `mint_authority` is then unused
+    pub minting_renounced: bool,
+}
+
+impl TolaState {
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 32 + 1 + 32 + 1 + 8 + 8 + 8 + 1;
+
+    pub fn assert_not_paused(&self) -> ProgramResult {
+        if self.paused {
+            return Err(VortexError::TokenPaused.into());
+        }
+        Ok(())
+    }
+
+    /// Reconcile the supply figures with the mint. All minting goes through
+    /// this program, so whatever `total_supply` exceeds the mint by was burned,
+    /// including burns that bypassed `Burn`.
+    pub fn sync_supply(&mut self, mint_account: &AccountInfo) -> ProgramResult {
+        if self.mint != *mint_account.key {
+            return Err(VortexError::InvalidAccount.into());
+        }
+        let mint = spl_token::state::Mint::unpack(&mint_account.data.borrow())?;
+        self.circulating_supply = mint.supply;
+        self.total_burned = self
+            .total_supply
+            .checked_sub(mint.supply)
+            .ok_or(VortexError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct VestingSchedule {
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub start_timestamp: i64,
+    pub cliff_timestamp: i64,
+    pub duration: i64,
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub revocable: bool,
+    pub revoked: bool,
+    pub schedule_id: u64,
+}
+
+impl VestingSchedule {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 32 + 1 + 1 + 8;
+
+    pub fn signer_seeds<'a>(&'a self, schedule_id: &'a [u8; 8], bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [VESTING_SEED, self.beneficiary.as_ref(), schedule_id, bump]
+    }
+
+    /// Amount unlocked at `now`: nothing before the cliff, then linear
+    /// from the start until `start_timestamp + duration`. A revoked
+    /// schedule keeps only what had vested when it was revoked.
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        if self.revoked {
+            return self.total_amount;
+        }
+        if now < self.cliff_timestamp || now < self.start_timestamp {
+            return 0;
+        }
+        let elapsed = now - self.start_timestamp;
+        if elapsed >= self.duration {
+            return self.total_amount;
+        }
+        (self.total_amount as u128 * elapsed as u128 / self.duration as u128) as u64
+    }
+
+    pub fn releasable_amount(&self, now: i64) -> u64 {
+        self.vested_amount(now).saturating_sub(self.released_amount)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct StakePool {
+    pub total_staked: u64,
+    pub reward_per_token_stored: u128,
+    pub last_update_timestamp: i64,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+    pub bump: u8,
+    pub reward_schedule: Pubkey,
+}
+
+impl StakePool {
+    pub const LEN: usize = 8 + 16 + 8 + 32 + 32 + 1 + 32;
+
+    /// Accumulated rewards per staked token at `now`, scaled by `REWARD_PRECISION`
+    pub fn reward_per_token(&self, now: i64, schedule: &RewardSchedule) -> Result<u128, ProgramError> {
+        if self.total_staked == 0 || now <= self.last_update_timestamp {
+            return Ok(self.reward_per_token_stored);
+        }
+        let emitted = schedule.emitted_between(self.last_update_timestamp, now)?;
+        let accrued = (emitted as u128)
+            .checked_mul(REWARD_PRECISION)
+            .ok_or(VortexError::MathOverflow)?
+            / self.total_staked as u128;
+        self.reward_per_token_stored
+            .checked_add(accrued)
+            .ok_or_else(|| VortexError::MathOverflow.into())
+    }
+
+    pub fn update(&mut self, now: i64, schedule: &RewardSchedule) -> ProgramResult {
+        self.reward_per_token_stored = self.reward_per_token(now, schedule)?;
+        self.last_update_timestamp = self.last_update_timestamp.max(now);
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RewardEpoch {
+    pub duration: i64,
+    /// Rewards emitted per second across all stakers
+    pub rate: u64,
+}
+
+impl RewardEpoch {
+    pub const LEN: usize = 8 + 8;
+}
+
+/// Staking emissions as back-to-back epochs from `start_timestamp`.
+/// Nothing is emitted once the last epoch ends.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RewardSchedule {
+    pub start_timestamp: i64,
+    pub bump: u8,
+    pub epochs: Vec<RewardEpoch>,
+}
+
+impl RewardSchedule {
+    pub fn len(num_epochs: usize) -> usize {
+        8 + 1 + 4 + num_epochs * RewardEpoch::LEN
+    }
+
+    pub fn end_timestamp(&self) -> i64 {
+        self.epochs
+            .iter()
+            .fold(self.start_timestamp, |end, epoch| end.saturating_add(epoch.duration))
+    }
+
+    /// Rewards emitted over `[from, to)`
+    pub fn emitted_between(&self, from: i64, to: i64) -> Result<u64, ProgramError> {
+        let mut emitted: u64 = 0;
+        let mut epoch_start = self.start_timestamp;
+        for epoch in &self.epochs {
+            let epoch_end = epoch_start.saturating_add(epoch.duration);
+            let overlap = epoch_end.min(to).saturating_sub(epoch_start.max(from));
+            if overlap > 0 {
+                emitted = (overlap as u64)
+                    .checked_mul(epoch.rate)
+                    .and_then(|e| emitted.checked_add(e))
+                    .ok_or(VortexError::MathOverflow)?;
+            }
+            epoch_start = epoch_end;
+        }
+        Ok(emitted)
+    }
+
+    /// Total rewards the schedule has emitted by `timestamp`, for
+    /// reconciling against the reward vault
+    pub fn total_emitted(&self, timestamp: i64) -> Result<u64, ProgramError> {
+        self.emitted_between(self.start_timestamp, timestamp)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct StakeAccount {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub start_timestamp: i64,
+    pub lock_end: i64,
+    pub reward_per_token_paid: u128,
+    pub pending_rewards: u64,
+    pub bump: u8,
+    /// Voting power as of the last stake change; use `voting_power_at` for the live value
+    pub voting_power: u64,
+}
+
+impl StakeAccount {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 16 + 8 + 1 + 8;
+
+    /// veTOLA weight at `now`: the staked amount scaled by the remaining
+    /// lock over `MAX_STAKE_DURATION`, decaying linearly to zero at `lock_end`
+    pub fn voting_power_at(&self, now: i64) -> u64 {
+        let remaining = self.lock_end.saturating_sub(now).clamp(0, MAX_STAKE_DURATION);
+        (self.amount as u128 * remaining as u128 / MAX_STAKE_DURATION as u128) as u64
+    }
+
+    /// Move rewards accrued since the last checkpoint into `pending_rewards`.
+    /// The pool must already be updated to the current time.
+    pub fn settle(&mut self, pool: &StakePool) -> ProgramResult {
+        let delta = pool.reward_per_token_stored.saturating_sub(self.reward_per_token_paid);
+        let earned = (self.amount as u128)
+            .checked_mul(delta)
+            .ok_or(VortexError::MathOverflow)?
+            / REWARD_PRECISION;
+        self.pending_rewards = u64::try_from(earned)
+            .ok()
+            .and_then(|earned| self.pending_rewards.checked_add(earned))
+            .ok_or(VortexError::MathOverflow)?;
+        self.reward_per_token_paid = pool.reward_per_token_stored;
+        Ok(())
+    }
+}
+
+/// Read the current voting power of `voter` from their stake account.
+/// Governance passes the TOLA program id so the account can be checked
+/// to be a genuine stake PDA of that program.
+pub fn get_voting_power(
+    token_program_id: &Pubkey,
+    stake_account: &AccountInfo,
+    voter: &Pubkey,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    Ok(get_stake(token_program_id, stake_account, voter)?.voting_power_at(now))
+}
+
+/// Total TOLA locked in the stake pool of `token_program_id`, which bounds
+/// the voting power all stakes together can carry
+pub fn get_total_staked(token_program_id: &Pubkey, pool_account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(load_stake_pool(token_program_id, pool_account)?.total_staked)
+}
+
+/// Read the stake of `voter`, checked as in `get_voting_power`, for
+/// callers that also need to know how long it stays locked
+pub fn get_stake(
+    token_program_id: &Pubkey,
+    stake_account: &AccountInfo,
+    voter: &Pubkey,
+) -> Result<StakeAccount, ProgramError> {
+    if stake_account.owner != token_program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let stake = StakeAccount::try_from_slice(&stake_account.data.borrow())?;
+    // Another voter's bump can put the address on the curve; that is still the wrong account
+    let expected = Pubkey::create_program_address(
+        &[STAKE_SEED, voter.as_ref(), &[stake.bump]],
+        token_program_id,
+    )
+    .map_err(|_| VortexError::InvalidAccount)?;
+    if expected != *stake_account.key || stake.owner != *voter {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(stake)
 }
 
 // Entry point
-entrypoint!(process_instruction);
+#[cfg(not(any(
+    feature = "no-entrypoint",
+    feature = "marketplace-program",
+    feature = "governance-program"
+)))]
+solana_program::entrypoint!(process_instruction);
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MerkleDistributor {
+    pub airdrop_id: u64,
+    pub merkle_root: [u8; 32],
+    pub vault: Pubkey,
+    pub num_leaves: u64,
+    pub total_amount: u64,
+    pub total_claimed: u64,
+    pub num_claimed: u64,
+    pub bump: u8,
+}
+
+impl MerkleDistributor {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    /// Bytes needed for a claim bitmap covering `num_leaves`
+    pub fn bitmap_len(num_leaves: u64) -> usize {
+        num_leaves.div_ceil(8) as usize
+    }
+}
+
+/// Leaf of an airdrop tree: `keccak(index || claimant || amount)`, integers little-endian
+pub fn airdrop_leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[&index.to_le_bytes(), claimant.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Check a Merkle proof. Sibling pairs are hashed in sorted order, so
+/// proofs carry no left/right flags.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).to_bytes()
+        } else {
+            keccak::hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
 
 // Program logic
 pub fn process_instruction(
@@ -68,18 +645,98 @@ pub fn process_instruction(
     let instruction = TolaInstruction::try_from_slice(instruction_data)?;
 
     match instruction {
-        TolaInstruction::Initialize { total_supply } => {
+        TolaInstruction::Initialize { total_supply, max_supply } => {
             msg!("Instruction: Initialize");
-            process_initialize(program_id, accounts, total_supply)
+            process_initialize(program_id, accounts, total_supply, max_supply)
         }
-        TolaInstruction::CreateVesting { amount, start_timestamp, duration } => {
+        TolaInstruction::CreateVesting {
+            schedule_id,
+            amount,
+            start_timestamp,
+            duration,
+            cliff_duration,
+            revocable,
+        } => {
             msg!("Instruction: Create Vesting");
-            process_create_vesting(program_id, accounts, amount, start_timestamp, duration)
+            process_create_vesting(
+                program_id,
+                accounts,
+                schedule_id,
+                amount,
+                start_timestamp,
+                duration,
+                cliff_duration,
+                revocable,
+            )
         }
         TolaInstruction::Stake { amount, duration } => {
             msg!("Instruction: Stake");
             process_stake(program_id, accounts, amount, duration)
         }
+        TolaInstruction::ClaimVested {} => {
+            msg!("Instruction: Claim Vested");
+            process_claim_vested(program_id, accounts)
+        }
+        TolaInstruction::RevokeVesting {} => {
+            msg!("Instruction: Revoke Vesting");
+            process_revoke_vesting(program_id, accounts)
+        }
+        TolaInstruction::InitializeStakePool {} => {
+            msg!("Instruction: Initialize Stake Pool");
+            process_initialize_stake_pool(program_id, accounts)
+        }
+        TolaInstruction::FundRewards { amount } => {
+            msg!("Instruction: Fund Rewards");
+            process_fund_rewards(program_id, accounts, amount)
+        }
+        TolaInstruction::Unstake {} => {
+            msg!("Instruction: Unstake");
+            process_unstake(program_id, accounts)
+        }
+        TolaInstruction::ClaimRewards {} => {
+            msg!("Instruction: Claim Rewards");
+            process_claim_rewards(program_id, accounts)
+        }
+        TolaInstruction::Pause {} => {
+            msg!("Instruction: Pause");
+            process_set_paused(program_id, accounts, true)
+        }
+        TolaInstruction::Unpause {} => {
+            msg!("Instruction: Unpause");
+            process_set_paused(program_id, accounts, false)
+        }
+        TolaInstruction::Burn { amount } => {
+            msg!("Instruction: Burn");
+            process_burn(program_id, accounts, amount)
+        }
+        TolaInstruction::SetMintAuthority { new_authority } => {
+            msg!("Instruction: Set Mint Authority");
+            process_set_mint_authority(program_id, accounts, new_authority)
+        }
+        TolaInstruction::MintTo { amount } => {
+            msg!("Instruction: Mint To");
+            process_mint_to(program_id, accounts, amount)
+        }
+        TolaInstruction::CreateDistributor { airdrop_id, merkle_root, total_amount, num_leaves } => {
+            msg!("Instruction: Create Distributor");
+            process_create_distributor(program_id, accounts, airdrop_id, merkle_root, total_amount, num_leaves)
+        }
+        TolaInstruction::ClaimAirdrop { index, amount, proof } => {
+            msg!("Instruction: Claim Airdrop");
+            process_claim_airdrop(program_id, accounts, index, amount, proof)
+        }
+        TolaInstruction::AppendRewardEpochs { count, epoch_duration, initial_rate, step_down_bps } => {
+            msg!("Instruction: Append Reward Epochs");
+            process_append_reward_epochs(program_id, accounts, count, epoch_duration, initial_rate, step_down_bps)
+        }
+        TolaInstruction::SyncSupply {} => {
+            msg!("Instruction: Sync Supply");
+            process_sync_supply(program_id, accounts)
+        }
+        TolaInstruction::SetAdmin { new_admin } => {
+            msg!("Instruction: Set Admin");
+            process_set_admin(program_id, accounts, new_admin)
+        }
     }
 }
 
@@ -88,43 +745,1288 @@ fn process_initialize(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     total_supply: u64,
+    max_supply: u64,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let initializer = next_account_info(account_info_iter)?;
     let mint_account = next_account_info(account_info_iter)?;
     let rent_account = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
 
     // Verify the initializer is the signer
     if !initializer.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+    // The initializer becomes admin and mint authority, so it must be whoever deployed the program
+    if upgrade_authority(program_id, program_data)? != Some(*initializer.key) {
+        return Err(VortexError::Unauthorized.into());
+    }
+
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if total_supply > max_supply {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let (state_key, state_bump) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint_key, mint_bump) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+    let (treasury_key, treasury_bump) = Pubkey::find_program_address(&[TREASURY_SEED], program_id);
+    if *state_account.key != state_key
+        || *mint_account.key != mint_key
+        || *treasury_account.key != treasury_key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Refuse to run twice
+    if state_account.owner == program_id && !state_account.data_is_empty() {
+        let state = TolaState::try_from_slice(&state_account.data.borrow())?;
+        if state.initialized {
+            return Err(VortexError::AlreadyInitialized.into());
+        }
+    }
+
+    // Create the mint, with the state PDA as mint authority
+    create_pda_account(
+        initializer,
+        mint_account,
+        system_program,
+        &spl_token::id(),
+        spl_token::state::Mint::LEN,
+        &[MINT_SEED, &[mint_bump]],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::initialize_mint(
+            token_program.key,
+            mint_account.key,
+            &state_key,
+            None,
+            TOLA_DECIMALS,
+        )?,
+        &[mint_account.clone(), rent_account.clone(), token_program.clone()],
+        &[],
+    )?;
+
+    // Create the treasury token account that receives the supply
+    create_pda_account(
+        initializer,
+        treasury_account,
+        system_program,
+        &spl_token::id(),
+        spl_token::state::Account::LEN,
+        &[TREASURY_SEED, &[treasury_bump]],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::initialize_account(
+            token_program.key,
+            treasury_account.key,
+            mint_account.key,
+            &state_key,
+        )?,
+        &[
+            treasury_account.clone(),
+            mint_account.clone(),
+            state_account.clone(),
+            rent_account.clone(),
+            token_program.clone(),
+        ],
+        &[],
+    )?;
+
+    // Mint the total supply into the treasury
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            token_program.key,
+            mint_account.key,
+            treasury_account.key,
+            &state_key,
+            &[],
+            total_supply,
+        )?,
+        &[
+            mint_account.clone(),
+            treasury_account.clone(),
+            state_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[STATE_SEED, &[state_bump]]],
+    )?;
+
+    // Persist the program state
+    if state_account.data_is_empty() {
+        create_pda_account(
+            initializer,
+            state_account,
+            system_program,
+            program_id,
+            TolaState::LEN,
+            &[STATE_SEED, &[state_bump]],
+        )?;
+    }
+    let state = TolaState {
+        total_supply,
+        mint_authority: *initializer.key,
+        initialized: true,
+        mint: mint_key,
+        treasury: treasury_key,
+        bump: state_bump,
+        admin: *initializer.key,
+        paused: false,
+        total_burned: 0,
+        circulating_supply: total_supply,
+        max_supply,
+        minting_renounced: false,
+    };
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
 
-    // Initialize token mint
-    // Implementation details to be added
+    emit(VortexEvent::TokenInitialized {
+        mint: mint_key,
+        total_supply,
+    });
 
     Ok(())
 }
 
+// Load the TOLA state, checking it is the initialized program PDA
+fn load_state(program_id: &Pubkey, state_account: &AccountInfo) -> Result<TolaState, ProgramError> {
+    if state_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let state = TolaState::try_from_slice(&state_account.data.borrow())?;
+    if !state.initialized {
+        return Err(VortexError::InvalidState.into());
+    }
+    let expected = Pubkey::create_program_address(&[STATE_SEED, &[state.bump]], program_id)?;
+    if expected != *state_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(state)
+}
+
 // Create vesting schedule
+#[allow(clippy::too_many_arguments)]
 fn process_create_vesting(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+    schedule_id: u64,
     amount: u64,
     start_timestamp: i64,
     duration: i64,
+    cliff_duration: i64,
+    revocable: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let vesting_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let beneficiary = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let state = load_state(program_id, state_account)?;
+    state.assert_not_paused()?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if state.treasury != *treasury_account.key || state.mint != *mint_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    if amount == 0 || duration <= 0 || cliff_duration < 0 || cliff_duration > duration {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let id_bytes = schedule_id.to_le_bytes();
+    let (vesting_key, vesting_bump) =
+        Pubkey::find_program_address(&[VESTING_SEED, beneficiary.key.as_ref(), &id_bytes], program_id);
+    let (escrow_key, escrow_bump) =
+        Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting_key.as_ref()], program_id);
+    if *vesting_account.key != vesting_key || *escrow_account.key != escrow_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    create_pda_account(
+        admin,
+        vesting_account,
+        system_program,
+        program_id,
+        VestingSchedule::LEN,
+        &[VESTING_SEED, beneficiary.key.as_ref(), &id_bytes, &[vesting_bump]],
+    )?;
+    create_token_account_pda(
+        admin,
+        escrow_account,
+        mint_account,
+        &vesting_key,
+        system_program,
+        token_program,
+        &[VESTING_ESCROW_SEED, vesting_key.as_ref(), &[escrow_bump]],
+    )?;
+
+    // Move the allocation out of the treasury into escrow
+    transfer_tokens(
+        token_program,
+        treasury_account,
+        escrow_account,
+        state_account,
+        amount,
+        &[&[STATE_SEED, &[state.bump]]],
+    )?;
+
+    let schedule = VestingSchedule {
+        beneficiary: *beneficiary.key,
+        mint: state.mint,
+        total_amount: amount,
+        released_amount: 0,
+        start_timestamp,
+        cliff_timestamp: start_timestamp
+            .checked_add(cliff_duration)
+            .ok_or(VortexError::InvalidInstruction)?,
+        duration,
+        bump: vesting_bump,
+        authority: *admin.key,
+        revocable,
+        revoked: false,
+        schedule_id,
+    };
+    schedule.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::VestingCreated {
+        beneficiary: *beneficiary.key,
+        amount,
+        start_time: start_timestamp,
+        duration,
+    });
+
+    Ok(())
+}
+
+// Release vested tokens to the beneficiary
+fn process_claim_vested(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
 ) -> ProgramResult {
-    // Implementation details to be added
+    let account_info_iter = &mut accounts.iter();
+    let beneficiary = next_account_info(account_info_iter)?;
+    let vesting_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let beneficiary_token_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+
+    if !beneficiary.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_state(program_id, state_account)?.assert_not_paused()?;
+
+    if vesting_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut schedule = VestingSchedule::try_from_slice(&vesting_account.data.borrow())?;
+    if schedule.beneficiary != *beneficiary.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    let (escrow_key, _) =
+        Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting_account.key.as_ref()], program_id);
+    if *escrow_account.key != escrow_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let amount = schedule.releasable_amount(now);
+    if amount == 0 {
+        return Err(VortexError::NothingToClaim.into());
+    }
+
+    transfer_tokens(
+        token_program,
+        escrow_account,
+        beneficiary_token_account,
+        vesting_account,
+        amount,
+        &[&schedule.signer_seeds(&schedule.schedule_id.to_le_bytes(), &[schedule.bump])],
+    )?;
+
+    schedule.released_amount += amount;
+    schedule.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::VestedTokensClaimed {
+        beneficiary: *beneficiary.key,
+        amount,
+    });
+
     Ok(())
 }
 
-// Process staking
-fn process_stake(
+// Claw back the unvested part of a schedule
+fn process_revoke_vesting(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    amount: u64,
-    duration: i64,
 ) -> ProgramResult {
-    // Implementation details to be added
+    let account_info_iter = &mut accounts.iter();
+    let authority = next_account_info(account_info_iter)?;
+    let vesting_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let state = load_state(program_id, state_account)?;
+    state.assert_not_paused()?;
+    if state.treasury != *treasury_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    if vesting_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut schedule = VestingSchedule::try_from_slice(&vesting_account.data.borrow())?;
+    if schedule.authority != *authority.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if !schedule.revocable || schedule.revoked {
+        return Err(VortexError::InvalidState.into());
+    }
+    let (escrow_key, _) =
+        Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting_account.key.as_ref()], program_id);
+    if *escrow_account.key != escrow_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    // Freeze the schedule at what has vested so far
+    let vested = schedule.vested_amount(Clock::get()?.unix_timestamp);
+    let unvested = schedule.total_amount - vested;
+    schedule.total_amount = vested;
+    schedule.revoked = true;
+
+    if unvested > 0 {
+        transfer_tokens(
+            token_program,
+            escrow_account,
+            treasury_account,
+            vesting_account,
+            unvested,
+            &[&schedule.signer_seeds(&schedule.schedule_id.to_le_bytes(), &[schedule.bump])],
+        )?;
+    }
+
+    schedule.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::VestingRevoked {
+        beneficiary: schedule.beneficiary,
+        unvested_amount: unvested,
+    });
+
+    Ok(())
+}
+
+// Flip the pause switch
+fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut state = load_state(program_id, state_account)?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if state.paused == paused {
+        return Err(VortexError::InvalidState.into());
+    }
+
+    state.paused = paused;
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::PauseStatusChanged {
+        admin: *admin.key,
+        paused,
+    });
+
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Burn tokens and track the burned supply
+fn process_burn(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner = next_account_info(account_info_iter)?;
+    let token_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut state = load_state(program_id, state_account)?;
+    state.assert_not_paused()?;
+    if state.mint != *mint_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if amount == 0 {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    invoke(
+        &spl_token::instruction::burn(
+            token_program.key,
+            token_account.key,
+            mint_account.key,
+            owner.key,
+            &[],
+            amount,
+        )?,
+        &[
+            token_account.clone(),
+            mint_account.clone(),
+            owner.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    state.sync_supply(mint_account)?;
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::TokensBurned {
+        owner: *owner.key,
+        amount,
+        circulating_supply: state.circulating_supply,
+    });
+
+    Ok(())
+}
+
+// Permissionless refresh of the supply figures
+fn process_sync_supply(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let state_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+
+    let mut state = load_state(program_id, state_account)?;
+    state.sync_supply(mint_account)?;
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+// Rotate the admin
+fn process_set_admin(program_id: &Pubkey, accounts: &[AccountInfo], new_admin: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut state = load_state(program_id, state_account)?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+
+    state.admin = new_admin;
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::AdminChanged {
+        previous_admin: *admin.key,
+        new_admin,
+    });
+
+    Ok(())
+}
+
+// Rotate or renounce the mint authority
+fn process_set_mint_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Option<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut state = load_state(program_id, state_account)?;
+    if state.minting_renounced || state.mint_authority != *authority.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if state.mint != *mint_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    match new_authority {
+        // Nobody can sign as the default pubkey, so it would renounce minting
+        // without setting `minting_renounced` or clearing the SPL authority
+        Some(new_authority) if new_authority == Pubkey::default() => {
+            return Err(VortexError::InvalidInstruction.into());
+        }
+        Some(new_authority) => state.mint_authority = new_authority,
+        None => {
+            // Drop the SPL mint authority too, so no future upgrade can mint
+            invoke_signed(
+                &spl_token::instruction::set_authority(
+                    token_program.key,
+                    mint_account.key,
+                    None,
+                    spl_token::instruction::AuthorityType::MintTokens,
+                    state_account.key,
+                    &[],
+                )?,
+                &[mint_account.clone(), state_account.clone(), token_program.clone()],
+                &[&[STATE_SEED, &[state.bump]]],
+            )?;
+            state.mint_authority = Pubkey::default();
+            state.minting_renounced = true;
+        }
+    }
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::MintAuthorityChanged {
+        previous_authority: *authority.key,
+        new_authority,
+    });
+
+    Ok(())
+}
+
+// Mint new supply within the cap
+fn process_mint_to(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut state = load_state(program_id, state_account)?;
+    state.assert_not_paused()?;
+    if state.minting_renounced || state.mint_authority != *authority.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if state.mint != *mint_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let total_supply = state
+        .total_supply
+        .checked_add(amount)
+        .ok_or(VortexError::MathOverflow)?;
+    if total_supply > state.max_supply {
+        return Err(VortexError::SupplyCapExceeded.into());
+    }
+
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            token_program.key,
+            mint_account.key,
+            destination.key,
+            state_account.key,
+            &[],
+            amount,
+        )?,
+        &[
+            mint_account.clone(),
+            destination.clone(),
+            state_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[STATE_SEED, &[state.bump]]],
+    )?;
+
+    state.total_supply = total_supply;
+    state.sync_supply(mint_account)?;
+    state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::TokensMinted {
+        destination: *destination.key,
+        amount,
+        total_supply,
+    });
+
+    Ok(())
+}
+
+// Load the stake pool, checking it is the program PDA
+fn load_stake_pool(program_id: &Pubkey, pool_account: &AccountInfo) -> Result<StakePool, ProgramError> {
+    if pool_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let pool = StakePool::try_from_slice(&pool_account.data.borrow())?;
+    let expected = Pubkey::create_program_address(&[STAKE_POOL_SEED, &[pool.bump]], program_id)?;
+    if expected != *pool_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(pool)
+}
+
+// Load a stake account owned by `staker`
+fn load_stake_account(
+    program_id: &Pubkey,
+    stake_account: &AccountInfo,
+    staker: &Pubkey,
+) -> Result<StakeAccount, ProgramError> {
+    if stake_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let stake = StakeAccount::try_from_slice(&stake_account.data.borrow())?;
+    if stake.owner != *staker {
+        return Err(VortexError::Unauthorized.into());
+    }
+    Ok(stake)
+}
+
+// Load the reward schedule the pool accrues from
+fn load_reward_schedule(
+    program_id: &Pubkey,
+    pool: &StakePool,
+    schedule_account: &AccountInfo,
+) -> Result<RewardSchedule, ProgramError> {
+    if schedule_account.owner != program_id || *schedule_account.key != pool.reward_schedule {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(RewardSchedule::try_from_slice(&schedule_account.data.borrow())?)
+}
+
+// Create the stake pool
+fn process_initialize_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let stake_vault = next_account_info(account_info_iter)?;
+    let reward_vault = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let schedule_account = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let state = load_state(program_id, state_account)?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if state.mint != *mint_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let (pool_key, pool_bump) = Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id);
+    let (stake_vault_key, stake_vault_bump) = Pubkey::find_program_address(&[STAKE_VAULT_SEED], program_id);
+    let (reward_vault_key, reward_vault_bump) = Pubkey::find_program_address(&[REWARD_VAULT_SEED], program_id);
+    let (schedule_key, schedule_bump) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
+    if *pool_account.key != pool_key
+        || *stake_vault.key != stake_vault_key
+        || *reward_vault.key != reward_vault_key
+        || *schedule_account.key != schedule_key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    create_pda_account(
+        admin,
+        pool_account,
+        system_program,
+        program_id,
+        StakePool::LEN,
+        &[STAKE_POOL_SEED, &[pool_bump]],
+    )?;
+    create_token_account_pda(
+        admin,
+        stake_vault,
+        mint_account,
+        &pool_key,
+        system_program,
+        token_program,
+        &[STAKE_VAULT_SEED, &[stake_vault_bump]],
+    )?;
+    create_token_account_pda(
+        admin,
+        reward_vault,
+        mint_account,
+        &pool_key,
+        system_program,
+        token_program,
+        &[REWARD_VAULT_SEED, &[reward_vault_bump]],
+    )?;
+
+    create_pda_account(
+        admin,
+        schedule_account,
+        system_program,
+        program_id,
+        RewardSchedule::len(0),
+        &[REWARD_SCHEDULE_SEED, &[schedule_bump]],
+    )?;
+
+    // Emissions start with the pool; epochs are appended separately
+    let now = Clock::get()?.unix_timestamp;
+    let schedule = RewardSchedule {
+        start_timestamp: now,
+        bump: schedule_bump,
+        epochs: Vec::new(),
+    };
+    schedule.serialize(&mut &mut schedule_account.data.borrow_mut()[..])?;
+
+    let pool = StakePool {
+        total_staked: 0,
+        reward_per_token_stored: 0,
+        last_update_timestamp: now,
+        stake_vault: stake_vault_key,
+        reward_vault: reward_vault_key,
+        bump: pool_bump,
+        reward_schedule: schedule_key,
+    };
+    pool.serialize(&mut &mut pool_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+// Top up the reward vault from the treasury
+fn process_fund_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let reward_vault = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let state = load_state(program_id, state_account)?;
+    state.assert_not_paused()?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    let pool = load_stake_pool(program_id, pool_account)?;
+    if pool.reward_vault != *reward_vault.key || state.treasury != *treasury_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    transfer_tokens(
+        token_program,
+        treasury_account,
+        reward_vault,
+        state_account,
+        amount,
+        &[&[STATE_SEED, &[state.bump]]],
+    )
+}
+
+// Process staking
+fn process_stake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    duration: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let staker = next_account_info(account_info_iter)?;
+    let stake_account = next_account_info(account_info_iter)?;
+    let staker_token_account = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let stake_vault = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let schedule_account = next_account_info(account_info_iter)?;
+
+    if !staker.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_state(program_id, state_account)?.assert_not_paused()?;
+
+    if amount == 0 || duration <= 0 || duration > MAX_STAKE_DURATION {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let mut pool = load_stake_pool(program_id, pool_account)?;
+    if pool.stake_vault != *stake_vault.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let (stake_key, stake_bump) =
+        Pubkey::find_program_address(&[STAKE_SEED, staker.key.as_ref()], program_id);
+    if *stake_account.key != stake_key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    pool.update(now, &load_reward_schedule(program_id, &pool, schedule_account)?)?;
+
+    // Open the stake account on first use, otherwise top it up
+    let mut stake = if stake_account.data_is_empty() {
+        create_pda_account(
+            staker,
+            stake_account,
+            system_program,
+            program_id,
+            StakeAccount::LEN,
+            &[STAKE_SEED, staker.key.as_ref(), &[stake_bump]],
+        )?;
+        StakeAccount {
+            owner: *staker.key,
+            amount: 0,
+            start_timestamp: now,
+            lock_end: now,
+            reward_per_token_paid: pool.reward_per_token_stored,
+            pending_rewards: 0,
+            bump: stake_bump,
+            voting_power: 0,
+        }
+    } else {
+        load_stake_account(program_id, stake_account, staker.key)?
+    };
+    stake.settle(&pool)?;
+
+    transfer_tokens(token_program, staker_token_account, stake_vault, staker, amount, &[])?;
+
+    let lock_end = now.checked_add(duration).ok_or(VortexError::MathOverflow)?;
+    if stake.amount == 0 {
+        stake.start_timestamp = now;
+    }
+    stake.amount = stake.amount.checked_add(amount).ok_or(VortexError::MathOverflow)?;
+    stake.lock_end = stake.lock_end.max(lock_end);
+    stake.voting_power = stake.voting_power_at(now);
+    pool.total_staked = pool.total_staked.checked_add(amount).ok_or(VortexError::MathOverflow)?;
+
+    stake.serialize(&mut &mut stake_account.data.borrow_mut()[..])?;
+    pool.serialize(&mut &mut pool_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::StakeCreated {
+        staker: *staker.key,
+        amount,
+        duration,
+    });
+
+    Ok(())
+}
+
+// Withdraw an unlocked stake
+fn process_unstake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let staker = next_account_info(account_info_iter)?;
+    let stake_account = next_account_info(account_info_iter)?;
+    let staker_token_account = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let stake_vault = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let schedule_account = next_account_info(account_info_iter)?;
+
+    if !staker.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_state(program_id, state_account)?.assert_not_paused()?;
+
+    let mut pool = load_stake_pool(program_id, pool_account)?;
+    if pool.stake_vault != *stake_vault.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut stake = load_stake_account(program_id, stake_account, staker.key)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if now < stake.lock_end {
+        return Err(VortexError::StakeLocked.into());
+    }
+    if stake.amount == 0 {
+        return Err(VortexError::InsufficientFunds.into());
+    }
+
+    pool.update(now, &load_reward_schedule(program_id, &pool, schedule_account)?)?;
+    stake.settle(&pool)?;
+
+    let amount = stake.amount;
+    transfer_tokens(
+        token_program,
+        stake_vault,
+        staker_token_account,
+        pool_account,
+        amount,
+        &[&[STAKE_POOL_SEED, &[pool.bump]]],
+    )?;
+
+    stake.amount = 0;
+    stake.voting_power = 0;
+    pool.total_staked -= amount;
+
+    stake.serialize(&mut &mut stake_account.data.borrow_mut()[..])?;
+    pool.serialize(&mut &mut pool_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::Unstaked {
+        staker: *staker.key,
+        amount,
+    });
+
+    Ok(())
+}
+
+// Pay out accrued rewards
+fn process_claim_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let staker = next_account_info(account_info_iter)?;
+    let stake_account = next_account_info(account_info_iter)?;
+    let staker_token_account = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let reward_vault = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let schedule_account = next_account_info(account_info_iter)?;
+
+    if !staker.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_state(program_id, state_account)?.assert_not_paused()?;
+
+    let mut pool = load_stake_pool(program_id, pool_account)?;
+    if pool.reward_vault != *reward_vault.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut stake = load_stake_account(program_id, stake_account, staker.key)?;
+
+    let schedule = load_reward_schedule(program_id, &pool, schedule_account)?;
+    pool.update(Clock::get()?.unix_timestamp, &schedule)?;
+    stake.settle(&pool)?;
+
+    let amount = stake.pending_rewards;
+    if amount == 0 {
+        return Err(VortexError::NothingToClaim.into());
+    }
+    let available = spl_token::state::Account::unpack(&reward_vault.data.borrow())?.amount;
+    if available < amount {
+        return Err(VortexError::InsufficientFunds.into());
+    }
+
+    transfer_tokens(
+        token_program,
+        reward_vault,
+        staker_token_account,
+        pool_account,
+        amount,
+        &[&[STAKE_POOL_SEED, &[pool.bump]]],
+    )?;
+
+    stake.pending_rewards = 0;
+
+    stake.serialize(&mut &mut stake_account.data.borrow_mut()[..])?;
+    pool.serialize(&mut &mut pool_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::RewardsClaimed {
+        staker: *staker.key,
+        amount,
+    });
+
+    Ok(())
+}
+
+// Publish an airdrop and move its allocation out of the treasury
+fn process_create_distributor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    airdrop_id: u64,
+    merkle_root: [u8; 32],
+    total_amount: u64,
+    num_leaves: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let distributor_account = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let bitmap_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if num_leaves == 0 || num_leaves > MAX_AIRDROP_LEAVES || total_amount == 0 {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let state = load_state(program_id, state_account)?;
+    state.assert_not_paused()?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    if state.mint != *mint_account.key || state.treasury != *treasury_account.key {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    let id_bytes = airdrop_id.to_le_bytes();
+    let (distributor_key, distributor_bump) =
+        Pubkey::find_program_address(&[DISTRIBUTOR_SEED, &id_bytes], program_id);
+    let (vault_key, vault_bump) =
+        Pubkey::find_program_address(&[DISTRIBUTOR_VAULT_SEED, distributor_key.as_ref()], program_id);
+    let (bitmap_key, bitmap_bump) =
+        Pubkey::find_program_address(&[CLAIM_BITMAP_SEED, distributor_key.as_ref()], program_id);
+    if *distributor_account.key != distributor_key
+        || *vault_account.key != vault_key
+        || *bitmap_account.key != bitmap_key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+
+    create_pda_account(
+        admin,
+        distributor_account,
+        system_program,
+        program_id,
+        MerkleDistributor::LEN,
+        &[DISTRIBUTOR_SEED, &id_bytes, &[distributor_bump]],
+    )?;
+    create_pda_account(
+        admin,
+        bitmap_account,
+        system_program,
+        program_id,
+        MerkleDistributor::bitmap_len(num_leaves),
+        &[CLAIM_BITMAP_SEED, distributor_key.as_ref(), &[bitmap_bump]],
+    )?;
+    create_token_account_pda(
+        admin,
+        vault_account,
+        mint_account,
+        &distributor_key,
+        system_program,
+        token_program,
+        &[DISTRIBUTOR_VAULT_SEED, distributor_key.as_ref(), &[vault_bump]],
+    )?;
+
+    transfer_tokens(
+        token_program,
+        treasury_account,
+        vault_account,
+        state_account,
+        total_amount,
+        &[&[STATE_SEED, &[state.bump]]],
+    )?;
+
+    let distributor = MerkleDistributor {
+        airdrop_id,
+        merkle_root,
+        vault: vault_key,
+        num_leaves,
+        total_amount,
+        total_claimed: 0,
+        num_claimed: 0,
+        bump: distributor_bump,
+    };
+    distributor.serialize(&mut &mut distributor_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::DistributorCreated {
+        distributor: distributor_key,
+        airdrop_id,
+        total_amount,
+        num_leaves,
+    });
+
+    Ok(())
+}
+
+// Pay out one airdrop leaf and mark it claimed
+fn process_claim_airdrop(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    index: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let claimant = next_account_info(account_info_iter)?;
+    let distributor_account = next_account_info(account_info_iter)?;
+    let bitmap_account = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+
+    if !claimant.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_state(program_id, state_account)?.assert_not_paused()?;
+
+    if distributor_account.owner != program_id || bitmap_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut distributor = MerkleDistributor::try_from_slice(&distributor_account.data.borrow())?;
+    let id_bytes = distributor.airdrop_id.to_le_bytes();
+    let expected = Pubkey::create_program_address(
+        &[DISTRIBUTOR_SEED, &id_bytes, &[distributor.bump]],
+        program_id,
+    )?;
+    let (bitmap_key, _) =
+        Pubkey::find_program_address(&[CLAIM_BITMAP_SEED, distributor_account.key.as_ref()], program_id);
+    if expected != *distributor_account.key
+        || bitmap_key != *bitmap_account.key
+        || distributor.vault != *vault_account.key
+    {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    if index >= distributor.num_leaves {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let leaf = airdrop_leaf(index, claimant.key, amount);
+    if !verify_merkle_proof(&proof, &distributor.merkle_root, leaf) {
+        return Err(VortexError::InvalidProof.into());
+    }
+
+    {
+        let mut bitmap = bitmap_account.data.borrow_mut();
+        let byte = (index / 8) as usize;
+        let mask = 1u8 << (index % 8);
+        if bitmap[byte] & mask != 0 {
+            return Err(VortexError::AlreadyClaimed.into());
+        }
+        bitmap[byte] |= mask;
+    }
+
+    distributor.total_claimed = distributor
+        .total_claimed
+        .checked_add(amount)
+        .ok_or(VortexError::MathOverflow)?;
+    if distributor.total_claimed > distributor.total_amount {
+        return Err(VortexError::InsufficientFunds.into());
+    }
+    distributor.num_claimed += 1;
+
+    transfer_tokens(
+        token_program,
+        vault_account,
+        destination,
+        distributor_account,
+        amount,
+        &[&[DISTRIBUTOR_SEED, &id_bytes, &[distributor.bump]]],
+    )?;
+    distributor.serialize(&mut &mut distributor_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::AirdropClaimed {
+        claimant: *claimant.key,
+        index,
+        amount,
+    });
+
+    Ok(())
+}
+
+// Extend the emission schedule with stepped-down epochs
+fn process_append_reward_epochs(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    count: u16,
+    epoch_duration: i64,
+    initial_rate: u64,
+    step_down_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let schedule_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if count == 0 || epoch_duration <= 0 || step_down_bps > 10_000 {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    let state = load_state(program_id, state_account)?;
+    if state.admin != *admin.key {
+        return Err(VortexError::Unauthorized.into());
+    }
+    let (schedule_key, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
+    if *schedule_account.key != schedule_key || schedule_account.owner != program_id {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    let mut schedule = RewardSchedule::try_from_slice(&schedule_account.data.borrow())?;
+
+    // Never emit retroactively: a lapsed schedule gets an empty epoch up to now
+    let now = Clock::get()?.unix_timestamp;
+    let end = schedule.end_timestamp();
+    if end < now {
+        schedule.epochs.push(RewardEpoch { duration: now - end, rate: 0 });
+    }
+    let mut rate = initial_rate;
+    for _ in 0..count {
+        schedule.epochs.push(RewardEpoch { duration: epoch_duration, rate });
+        rate = (rate as u128 * (10_000 - step_down_bps) as u128 / 10_000) as u64;
+    }
+    if schedule.epochs.len() > MAX_REWARD_EPOCHS {
+        return Err(VortexError::InvalidInstruction.into());
+    }
+
+    resize_account(admin, schedule_account, system_program, RewardSchedule::len(schedule.epochs.len()))?;
+    schedule.serialize(&mut &mut schedule_account.data.borrow_mut()[..])?;
+
+    emit(VortexEvent::RewardEpochsAppended {
+        count,
+        end_timestamp: schedule.end_timestamp(),
+    });
+
+    Ok(())
+}
@@ -1,172 +1,1175 @@
 use super::*;
+use crate::tests::helpers::*;
 use solana_program::{
-    program_pack::Pack,
-    pubkey::Pubkey,
-    account_info::AccountInfo,
-    rent::Rent,
-    sysvar::Sysvar,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    system_program,
+    sysvar,
 };
 use solana_program_test::*;
 use solana_sdk::{
     signature::{Keypair, Signer},
-    transaction::Transaction,
-    account::Account,
+    transaction::TransactionError,
 };
 
-#[tokio::test]
-async fn test_token_initialization() {
-    let program_id = Pubkey::new_unique();
-    let mut program_test = ProgramTest::new(
+fn program_test(program_id: Pubkey) -> ProgramTest {
+    ProgramTest::new(
         "vortex_contracts",
         program_id,
         processor!(process_instruction),
-    );
+    )
+}
 
-    // Create accounts
-    let initializer = Keypair::new();
-    let mint_account = Keypair::new();
-    
-    // Add accounts to test environment
-    program_test.add_account(
-        initializer.pubkey(),
-        Account {
-            lamports: 1_000_000_000,
-            data: vec![],
-            owner: program_id,
-            ..Account::default()
-        },
-    );
+fn initialize_instruction(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    total_supply: u64,
+    max_supply: u64,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::Initialize { total_supply, max_supply }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*initializer, true),
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(state, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+    )
+}
+
+fn vesting_address(program_id: &Pubkey, beneficiary: &Pubkey, schedule_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[VESTING_SEED, beneficiary.as_ref(), &schedule_id.to_le_bytes()], program_id).0
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_vesting_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    beneficiary: &Pubkey,
+    schedule_id: u64,
+    amount: u64,
+    start_timestamp: i64,
+    duration: i64,
+    cliff_duration: i64,
+    revocable: bool,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], program_id);
+    let vesting = vesting_address(program_id, beneficiary, schedule_id);
+    let (escrow, _) = Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::CreateVesting {
+            schedule_id,
+            amount,
+            start_timestamp,
+            duration,
+            cliff_duration,
+            revocable,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(*beneficiary, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn claim_vested_instruction(
+    program_id: &Pubkey,
+    beneficiary: &Pubkey,
+    schedule_id: u64,
+    destination: &Pubkey,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let vesting = vesting_address(program_id, beneficiary, schedule_id);
+    let (escrow, _) = Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::ClaimVested {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*beneficiary, true),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(state, false),
+        ],
+    )
+}
+
+fn revoke_vesting_instruction(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    beneficiary: &Pubkey,
+    schedule_id: u64,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], program_id);
+    let vesting = vesting_address(program_id, beneficiary, schedule_id);
+    let (escrow, _) = Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::RevokeVesting {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn initialize_stake_pool_instruction(program_id: &Pubkey, admin: &Pubkey) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+    let (pool, _) = Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id);
+    let (stake_vault, _) = Pubkey::find_program_address(&[STAKE_VAULT_SEED], program_id);
+    let (reward_vault, _) = Pubkey::find_program_address(&[REWARD_VAULT_SEED], program_id);
+    let (schedule, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::InitializeStakePool {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(stake_vault, false),
+            AccountMeta::new(reward_vault, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(schedule, false),
+        ],
+    )
+}
 
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+fn append_reward_epochs_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    count: u16,
+    epoch_duration: i64,
+    initial_rate: u64,
+    step_down_bps: u16,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (schedule, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
 
-    // Create initialization instruction
-    let init_instruction = TolaInstruction::Initialize {
-        total_supply: 1_000_000_000,
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::AppendRewardEpochs { count, epoch_duration, initial_rate, step_down_bps }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(schedule, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn fund_rewards_instruction(program_id: &Pubkey, admin: &Pubkey, amount: u64) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], program_id);
+    let (pool, _) = Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id);
+    let (reward_vault, _) = Pubkey::find_program_address(&[REWARD_VAULT_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::FundRewards { amount }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(pool, false),
+            AccountMeta::new(reward_vault, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn stake_instruction(
+    program_id: &Pubkey,
+    staker: &Pubkey,
+    token_account: &Pubkey,
+    amount: u64,
+    duration: i64,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (stake_account, _) = Pubkey::find_program_address(&[STAKE_SEED, staker.as_ref()], program_id);
+    let (pool, _) = Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id);
+    let (stake_vault, _) = Pubkey::find_program_address(&[STAKE_VAULT_SEED], program_id);
+    let (schedule, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::Stake { amount, duration }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*staker, true),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(stake_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(schedule, false),
+        ],
+    )
+}
+
+fn unstake_instruction(program_id: &Pubkey, staker: &Pubkey, token_account: &Pubkey) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (stake_account, _) = Pubkey::find_program_address(&[STAKE_SEED, staker.as_ref()], program_id);
+    let (pool, _) = Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id);
+    let (stake_vault, _) = Pubkey::find_program_address(&[STAKE_VAULT_SEED], program_id);
+    let (schedule, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::Unstake {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*staker, true),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(stake_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(schedule, false),
+        ],
+    )
+}
+
+fn claim_rewards_instruction(program_id: &Pubkey, staker: &Pubkey, token_account: &Pubkey) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (stake_account, _) = Pubkey::find_program_address(&[STAKE_SEED, staker.as_ref()], program_id);
+    let (pool, _) = Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id);
+    let (reward_vault, _) = Pubkey::find_program_address(&[REWARD_VAULT_SEED], program_id);
+    let (schedule, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::ClaimRewards {}.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*staker, true),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(reward_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(schedule, false),
+        ],
+    )
+}
+
+fn set_paused_instruction(program_id: &Pubkey, admin: &Pubkey, paused: bool) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let instruction = if paused {
+        TolaInstruction::Pause {}
+    } else {
+        TolaInstruction::Unpause {}
     };
 
-    // Create transaction
-    let mut transaction = Transaction::new_with_payer(
-        &[init_instruction],
-        Some(&payer.pubkey()),
-    );
-    transaction.sign(&[&payer], recent_blockhash);
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(state, false),
+        ],
+    )
+}
+
+fn set_admin_instruction(program_id: &Pubkey, admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::SetAdmin { new_admin: *new_admin }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(state, false),
+        ],
+    )
+}
+
+fn burn_instruction(program_id: &Pubkey, owner: &Pubkey, token_account: &Pubkey, amount: u64) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::Burn { amount }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
 
-    // Process transaction
-    banks_client.process_transaction(transaction).await.unwrap();
+fn sync_supply_instruction(program_id: &Pubkey) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
 
-    // Verify initialization
-    let mint_account = banks_client
-        .get_account(mint_account.pubkey())
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::SyncSupply {}.try_to_vec().unwrap(),
+        vec![AccountMeta::new(state, false), AccountMeta::new_readonly(mint, false)],
+    )
+}
+
+fn set_mint_authority_instruction(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    new_authority: Option<Pubkey>,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::SetMintAuthority { new_authority }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(state, false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn mint_to_instruction(program_id: &Pubkey, authority: &Pubkey, destination: &Pubkey, amount: u64) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::MintTo { amount }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+fn create_distributor_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    airdrop_id: u64,
+    merkle_root: [u8; 32],
+    total_amount: u64,
+    num_leaves: u64,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], program_id);
+    let (distributor, _) =
+        Pubkey::find_program_address(&[DISTRIBUTOR_SEED, &airdrop_id.to_le_bytes()], program_id);
+    let (vault, _) = Pubkey::find_program_address(&[DISTRIBUTOR_VAULT_SEED, distributor.as_ref()], program_id);
+    let (bitmap, _) = Pubkey::find_program_address(&[CLAIM_BITMAP_SEED, distributor.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::CreateDistributor { airdrop_id, merkle_root, total_amount, num_leaves }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(distributor, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(bitmap, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn claim_airdrop_instruction(
+    program_id: &Pubkey,
+    airdrop_id: u64,
+    claimant: &Pubkey,
+    destination: &Pubkey,
+    index: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], program_id);
+    let (distributor, _) =
+        Pubkey::find_program_address(&[DISTRIBUTOR_SEED, &airdrop_id.to_le_bytes()], program_id);
+    let (vault, _) = Pubkey::find_program_address(&[DISTRIBUTOR_VAULT_SEED, distributor.as_ref()], program_id);
+    let (bitmap, _) = Pubkey::find_program_address(&[CLAIM_BITMAP_SEED, distributor.as_ref()], program_id);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &TolaInstruction::ClaimAirdrop { index, amount, proof }.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*claimant, true),
+            AccountMeta::new(distributor, false),
+            AccountMeta::new(bitmap, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(state, false),
+        ],
+    )
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[&lo, &hi]).to_bytes()
+}
+
+// Initialize with room to mint as much again as the initial supply
+async fn initialize(context: &mut ProgramTestContext, program_id: &Pubkey, total_supply: u64) {
+    let payer = context.payer.pubkey();
+    set_upgrade_authority(context, program_id, &payer);
+    process(context, &[initialize_instruction(program_id, &payer, total_supply, total_supply * 2)], &[])
         .await
-        .unwrap()
         .unwrap();
+}
 
-    assert!(mint_account.data.len() > 0);
+#[tokio::test]
+async fn test_token_initialization() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let payer = context.payer.pubkey();
+    set_upgrade_authority(&mut context, &program_id, &payer);
+
+    // Only the upgrade authority may initialize, not whoever gets there first
+    let squatter = Keypair::new();
+    fund_lamports(&mut context, &squatter.pubkey()).await;
+    let err = process(
+        &mut context,
+        &[initialize_instruction(&program_id, &squatter.pubkey(), 1_000_000_000, 2_000_000_000)],
+        &[&squatter],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::Unauthorized);
+
+    process(&mut context, &[initialize_instruction(&program_id, &payer, 1_000_000_000, 2_000_000_000)], &[])
+        .await
+        .unwrap();
+
+    // Verify the mint and the treasury balance
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], &program_id);
+    let (state, _) = Pubkey::find_program_address(&[STATE_SEED], &program_id);
+
+    let mint_account = context.banks_client.get_account(mint).await.unwrap().unwrap();
+    let mint_data = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+    assert_eq!(mint_data.supply, 1_000_000_000);
+    assert_eq!(mint_data.mint_authority.unwrap(), state);
+
+    assert_eq!(token_balance(&mut context, &treasury).await, 1_000_000_000);
+
+    let state_account = context.banks_client.get_account(state).await.unwrap().unwrap();
+    let tola_state = TolaState::try_from_slice(&state_account.data).unwrap();
+    assert!(tola_state.initialized);
+    assert_eq!(tola_state.mint, mint);
+    assert_eq!(tola_state.mint_authority, payer);
+}
+
+#[tokio::test]
+async fn test_token_reinitialization_fails() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let payer = context.payer.pubkey();
+    initialize(&mut context, &program_id, 1_000_000_000).await;
+
+    // A second initialization must be rejected
+    let err = process(&mut context, &[initialize_instruction(&program_id, &payer, 5, 5)], &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    expect_error(err, VortexError::AlreadyInitialized);
 }
 
 #[tokio::test]
 async fn test_vesting_schedule() {
     let program_id = Pubkey::new_unique();
-    let mut program_test = ProgramTest::new(
-        "vortex_contracts",
-        program_id,
-        processor!(process_instruction),
-    );
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000_000).await;
 
     // Create accounts
-    let creator = Keypair::new();
-    let vesting_account = Keypair::new();
-    let token_account = Keypair::new();
-
-    // Add accounts to test environment
-    program_test.add_account(
-        creator.pubkey(),
-        Account {
-            lamports: 1_000_000_000,
-            data: vec![],
-            owner: program_id,
-            ..Account::default()
-        },
-    );
+    let beneficiary = Keypair::new();
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let beneficiary_tokens = create_token_account(&mut context, &mint, &beneficiary.pubkey(), 0);
 
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    // One year schedule with a three month cliff
+    let start = clock(&mut context).await.unix_timestamp;
+    let duration = 365 * 24 * 60 * 60;
+    let admin = context.payer.insecure_clone();
+    process(
+        &mut context,
+        &[create_vesting_instruction(
+            &program_id,
+            &admin.pubkey(),
+            &beneficiary.pubkey(),
+            0,
+            100_000,
+            start,
+            duration,
+            duration / 4,
+            false,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let vesting = vesting_address(&program_id, &beneficiary.pubkey(), 0);
+    let vesting_account = context.banks_client.get_account(vesting).await.unwrap().unwrap();
+    let schedule = VestingSchedule::try_from_slice(&vesting_account.data).unwrap();
+    assert_eq!(schedule.total_amount, 100_000);
+    assert_eq!(schedule.cliff_timestamp, start + duration / 4);
+    let (escrow, _) = Pubkey::find_program_address(&[VESTING_ESCROW_SEED, vesting.as_ref()], &program_id);
+    assert_eq!(token_balance(&mut context, &escrow).await, 100_000);
+
+    // Nothing can be claimed before the cliff
+    let claim = || claim_vested_instruction(&program_id, &beneficiary.pubkey(), 0, &beneficiary_tokens);
+    assert!(process(&mut context, &[claim()], &[&beneficiary]).await.is_err());
+
+    // Half way through the schedule half of the tokens are released
+    set_clock(&mut context, start + duration / 2).await;
+    process(&mut context, &[claim()], &[&beneficiary]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &beneficiary_tokens).await, 50_000);
+
+    // After the end everything is released
+    set_clock(&mut context, start + duration + 1).await;
+    process(&mut context, &[claim()], &[&beneficiary]).await.unwrap();
+    assert_eq!(token_balance(&mut context, &beneficiary_tokens).await, 100_000);
+    assert_eq!(token_balance(&mut context, &escrow).await, 0);
+}
 
-    // Create vesting instruction
-    let vesting_instruction = TolaInstruction::CreateVesting {
-        amount: 100_000,
-        start_timestamp: 1_000_000,
-        duration: 365 * 24 * 60 * 60, // 1 year
+#[test]
+fn test_vested_amount() {
+    let schedule = VestingSchedule {
+        beneficiary: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        total_amount: 1_000,
+        released_amount: 0,
+        start_timestamp: 100,
+        cliff_timestamp: 150,
+        duration: 400,
+        bump: 0,
+        authority: Pubkey::new_unique(),
+        revocable: false,
+        revoked: false,
+        schedule_id: 0,
     };
 
-    // Create transaction
-    let mut transaction = Transaction::new_with_payer(
-        &[vesting_instruction],
-        Some(&payer.pubkey()),
-    );
-    transaction.sign(&[&payer, &creator], recent_blockhash);
+    assert_eq!(schedule.vested_amount(0), 0);
+    assert_eq!(schedule.vested_amount(149), 0);
+    assert_eq!(schedule.vested_amount(150), 125);
+    assert_eq!(schedule.vested_amount(300), 500);
+    assert_eq!(schedule.vested_amount(500), 1_000);
+    assert_eq!(schedule.vested_amount(i64::MAX), 1_000);
+}
 
-    // Process transaction
-    banks_client.process_transaction(transaction).await.unwrap();
+#[tokio::test]
+async fn test_revoke_vesting() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000_000).await;
 
-    // Verify vesting schedule
-    let vesting_account = banks_client
-        .get_account(vesting_account.pubkey())
-        .await
-        .unwrap()
-        .unwrap();
+    let advisor = Keypair::new();
+    let founder = Keypair::new();
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], &program_id);
+    let advisor_tokens = create_token_account(&mut context, &mint, &advisor.pubkey(), 0);
 
-    assert!(vesting_account.data.len() > 0);
+    let start = clock(&mut context).await.unix_timestamp;
+    let duration = 1_000;
+    let admin = context.payer.insecure_clone();
+    let grant = |beneficiary: &Pubkey, schedule_id: u64, amount: u64, revocable: bool| {
+        let admin = admin.pubkey();
+        create_vesting_instruction(&program_id, &admin, beneficiary, schedule_id, amount, start, duration, 0, revocable)
+    };
+    process(
+        &mut context,
+        &[
+            grant(&advisor.pubkey(), 0, 100_000, true),
+            grant(&founder.pubkey(), 0, 100_000, false),
+            // A second, separate grant to the same advisor
+            grant(&advisor.pubkey(), 1, 50_000, false),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Only revocable schedules can be revoked
+    assert!(process(
+        &mut context,
+        &[revoke_vesting_instruction(&program_id, &admin.pubkey(), &founder.pubkey(), 0)],
+        &[],
+    )
+    .await
+    .is_err());
+
+    // Revoking at 40% returns the other 60% to the treasury
+    set_clock(&mut context, start + 400).await;
+    process(
+        &mut context,
+        &[revoke_vesting_instruction(&program_id, &admin.pubkey(), &advisor.pubkey(), 0)],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &treasury).await, 1_000_000_000 - 250_000 + 60_000);
+
+    // The vested part stays claimable, even after the original end, and
+    // the advisor's other grant is untouched by the revocation
+    set_clock(&mut context, start + duration * 2).await;
+    process(
+        &mut context,
+        &[claim_vested_instruction(&program_id, &advisor.pubkey(), 0, &advisor_tokens)],
+        &[&advisor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &advisor_tokens).await, 40_000);
+    process(
+        &mut context,
+        &[claim_vested_instruction(&program_id, &advisor.pubkey(), 1, &advisor_tokens)],
+        &[&advisor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &advisor_tokens).await, 90_000);
 }
 
 #[tokio::test]
 async fn test_staking() {
     let program_id = Pubkey::new_unique();
-    let mut program_test = ProgramTest::new(
-        "vortex_contracts",
-        program_id,
-        processor!(process_instruction),
-    );
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000_000).await;
+    let admin = context.payer.insecure_clone();
+    process(
+        &mut context,
+        &[
+            initialize_stake_pool_instruction(&program_id, &admin.pubkey()),
+            append_reward_epochs_instruction(&program_id, &admin.pubkey(), 1, MAX_STAKE_DURATION, 10, 0),
+            fund_rewards_instruction(&program_id, &admin.pubkey(), 1_000_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
 
     // Create accounts
     let staker = Keypair::new();
-    let stake_account = Keypair::new();
-    let token_account = Keypair::new();
-
-    // Add accounts to test environment
-    program_test.add_account(
-        staker.pubkey(),
-        Account {
-            lamports: 1_000_000_000,
-            data: vec![],
-            owner: program_id,
-            ..Account::default()
-        },
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let token_account = create_token_account(&mut context, &mint, &staker.pubkey(), 50_000);
+    fund_lamports(&mut context, &staker.pubkey()).await;
+
+    // Stake everything for 30 days
+    let start = clock(&mut context).await.unix_timestamp;
+    let duration = 30 * 24 * 60 * 60;
+    set_clock(&mut context, start).await;
+    process(
+        &mut context,
+        &[stake_instruction(&program_id, &staker.pubkey(), &token_account, 50_000, duration)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    let (stake_account, _) = Pubkey::find_program_address(&[STAKE_SEED, staker.pubkey().as_ref()], &program_id);
+    let stake = context.banks_client.get_account(stake_account).await.unwrap().unwrap();
+    let stake = StakeAccount::try_from_slice(&stake.data).unwrap();
+    assert_eq!(stake.amount, 50_000);
+    assert_eq!(stake.lock_end, start + duration);
+    assert_eq!(stake.voting_power, 50_000 * duration as u64 / MAX_STAKE_DURATION as u64);
+
+    // As the only staker, 100 seconds at 10 per second earns 1000
+    set_clock(&mut context, start + 100).await;
+    process(
+        &mut context,
+        &[claim_rewards_instruction(&program_id, &staker.pubkey(), &token_account)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &token_account).await, 1_000);
+
+    // The stake cannot leave before the lock expires
+    let err = process(
+        &mut context,
+        &[unstake_instruction(&program_id, &staker.pubkey(), &token_account)],
+        &[&staker],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::StakeLocked as u32))
     );
 
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    set_clock(&mut context, start + duration).await;
+    process(
+        &mut context,
+        &[unstake_instruction(&program_id, &staker.pubkey(), &token_account)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &token_account).await, 51_000);
+}
+
+#[test]
+fn test_reward_per_token_is_shared_between_stakers() {
+    let schedule = RewardSchedule {
+        start_timestamp: 0,
+        bump: 0,
+        epochs: vec![RewardEpoch { duration: 100, rate: 100 }],
+    };
+    let mut pool = StakePool {
+        total_staked: 0,
+        reward_per_token_stored: 0,
+        last_update_timestamp: 0,
+        stake_vault: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        bump: 0,
+        reward_schedule: Pubkey::new_unique(),
+    };
+    let mut alice = StakeAccount {
+        owner: Pubkey::new_unique(),
+        amount: 200,
+        start_timestamp: 0,
+        lock_end: 0,
+        reward_per_token_paid: 0,
+        pending_rewards: 0,
+        bump: 0,
+        voting_power: 0,
+    };
+    pool.total_staked = 200;
 
-    // Create staking instruction
-    let stake_instruction = TolaInstruction::Stake {
-        amount: 50_000,
-        duration: 30 * 24 * 60 * 60, // 30 days
+    // Alice alone for 10 seconds, then Bob matches her stake for 10 more
+    pool.update(10, &schedule).unwrap();
+    let mut bob = StakeAccount {
+        owner: Pubkey::new_unique(),
+        reward_per_token_paid: pool.reward_per_token_stored,
+        ..alice
     };
+    pool.total_staked += 200;
+    pool.update(20, &schedule).unwrap();
+
+    alice.settle(&pool).unwrap();
+    bob.settle(&pool).unwrap();
+    assert_eq!(alice.pending_rewards, 1_000 + 500);
+    assert_eq!(bob.pending_rewards, 500);
+}
+
+#[test]
+fn test_reward_schedule_emissions() {
+    // Halving every 100 seconds from 1000 per second
+    let schedule = RewardSchedule {
+        start_timestamp: 1_000,
+        bump: 0,
+        epochs: vec![
+            RewardEpoch { duration: 100, rate: 1_000 },
+            RewardEpoch { duration: 100, rate: 500 },
+            RewardEpoch { duration: 100, rate: 250 },
+        ],
+    };
+    assert_eq!(schedule.end_timestamp(), 1_300);
+    assert_eq!(schedule.total_emitted(500).unwrap(), 0);
+    assert_eq!(schedule.total_emitted(1_050).unwrap(), 50_000);
+    assert_eq!(schedule.total_emitted(1_150).unwrap(), 100_000 + 25_000);
+    assert_eq!(schedule.total_emitted(5_000).unwrap(), 175_000);
+    assert_eq!(schedule.emitted_between(1_150, 1_250).unwrap(), 25_000 + 12_500);
+}
+
+#[test]
+fn test_voting_power_decays_with_remaining_lock() {
+    let stake = StakeAccount {
+        owner: Pubkey::new_unique(),
+        amount: 1_000,
+        start_timestamp: 0,
+        lock_end: MAX_STAKE_DURATION,
+        reward_per_token_paid: 0,
+        pending_rewards: 0,
+        bump: 0,
+        voting_power: 0,
+    };
+
+    assert_eq!(stake.voting_power_at(0), 1_000);
+    assert_eq!(stake.voting_power_at(MAX_STAKE_DURATION / 4), 750);
+    assert_eq!(stake.voting_power_at(MAX_STAKE_DURATION / 2), 500);
+    assert_eq!(stake.voting_power_at(MAX_STAKE_DURATION), 0);
+    assert_eq!(stake.voting_power_at(MAX_STAKE_DURATION * 2), 0);
+}
+
+#[tokio::test]
+async fn test_pause_blocks_staking() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000_000).await;
+    let admin = context.payer.insecure_clone();
+    process(
+        &mut context,
+        &[initialize_stake_pool_instruction(&program_id, &admin.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let staker = Keypair::new();
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let token_account = create_token_account(&mut context, &mint, &staker.pubkey(), 50_000);
+    fund_lamports(&mut context, &staker.pubkey()).await;
+
+    // Only the admin can pause
+    assert!(process(
+        &mut context,
+        &[set_paused_instruction(&program_id, &staker.pubkey(), true)],
+        &[&staker],
+    )
+    .await
+    .is_err());
+
+    process(&mut context, &[set_paused_instruction(&program_id, &admin.pubkey(), true)], &[])
+        .await
+        .unwrap();
+    let err = process(
+        &mut context,
+        &[stake_instruction(&program_id, &staker.pubkey(), &token_account, 50_000, 60)],
+        &[&staker],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::TokenPaused as u32))
+    );
+
+    process(&mut context, &[set_paused_instruction(&program_id, &admin.pubkey(), false)], &[])
+        .await
+        .unwrap();
+    process(
+        &mut context,
+        &[stake_instruction(&program_id, &staker.pubkey(), &token_account, 50_000, 60)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
 
-    // Create transaction
-    let mut transaction = Transaction::new_with_payer(
-        &[stake_instruction],
-        Some(&payer.pubkey()),
+    // Handing over the admin role takes the pause switch with it
+    let successor = Keypair::new();
+    let err = process(
+        &mut context,
+        &[set_admin_instruction(&program_id, &staker.pubkey(), &staker.pubkey())],
+        &[&staker],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::Unauthorized as u32))
     );
-    transaction.sign(&[&payer, &staker], recent_blockhash);
+    process(&mut context, &[set_admin_instruction(&program_id, &admin.pubkey(), &successor.pubkey())], &[])
+        .await
+        .unwrap();
+    assert!(process(&mut context, &[set_paused_instruction(&program_id, &admin.pubkey(), true)], &[])
+        .await
+        .is_err());
+    process(&mut context, &[set_paused_instruction(&program_id, &successor.pubkey(), true)], &[&successor])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_burn_tracks_circulating_supply() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000_000).await;
+
+    // Vest a grant so the holder owns real, minted tokens
+    let holder = Keypair::new();
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let token_account = create_token_account(&mut context, &mint, &holder.pubkey(), 0);
+    let start = clock(&mut context).await.unix_timestamp;
+    let admin = context.payer.insecure_clone();
+    let grant =
+        create_vesting_instruction(&program_id, &admin.pubkey(), &holder.pubkey(), 0, 10_000, start - 10, 1, 0, false);
+    process(&mut context, &[grant], &[])
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[claim_vested_instruction(&program_id, &holder.pubkey(), 0, &token_account)],
+        &[&holder],
+    )
+    .await
+    .unwrap();
+
+    process(
+        &mut context,
+        &[burn_instruction(&program_id, &holder.pubkey(), &token_account, 4_000)],
+        &[&holder],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(token_balance(&mut context, &token_account).await, 6_000);
+    let (state_key, _) = Pubkey::find_program_address(&[STATE_SEED], &program_id);
+    let state = context.banks_client.get_account(state_key).await.unwrap().unwrap();
+    let state = TolaState::try_from_slice(&state.data).unwrap();
+    assert_eq!(state.total_burned, 4_000);
+    assert_eq!(state.circulating_supply, 1_000_000_000 - 4_000);
 
-    // Process transaction
-    banks_client.process_transaction(transaction).await.unwrap();
+    let mint_account = context.banks_client.get_account(mint).await.unwrap().unwrap();
+    assert_eq!(spl_token::state::Mint::unpack(&mint_account.data).unwrap().supply, 1_000_000_000 - 4_000);
 
-    // Verify staking
-    let stake_account = banks_client
-        .get_account(stake_account.pubkey())
+    // Burning straight through the token program is picked up by the next sync
+    let direct_burn =
+        spl_token::instruction::burn(&spl_token::id(), &token_account, &mint, &holder.pubkey(), &[], 1_000).unwrap();
+    process(&mut context, &[direct_burn, sync_supply_instruction(&program_id)], &[&holder])
         .await
-        .unwrap()
         .unwrap();
+    let state = context.banks_client.get_account(state_key).await.unwrap().unwrap();
+    let state = TolaState::try_from_slice(&state.data).unwrap();
+    assert_eq!(state.total_burned, 5_000);
+    assert_eq!(state.circulating_supply, 1_000_000_000 - 5_000);
+}
+
+#[tokio::test]
+async fn test_mint_authority_and_supply_cap() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000).await;
+
+    let admin = context.payer.insecure_clone();
+    let rewards_multisig = Keypair::new();
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let destination = create_token_account(&mut context, &mint, &rewards_multisig.pubkey(), 0);
+
+    // Handing minting to the default pubkey would renounce it without saying so
+    let err = process(
+        &mut context,
+        &[set_mint_authority_instruction(&program_id, &admin.pubkey(), Some(Pubkey::default()))],
+        &[],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    expect_error(err, VortexError::InvalidInstruction);
 
-    assert!(stake_account.data.len() > 0);
-} 
\ No newline at end of file
+    // Hand minting over to the rewards key; the admin loses it
+    process(
+        &mut context,
+        &[set_mint_authority_instruction(&program_id, &admin.pubkey(), Some(rewards_multisig.pubkey()))],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert!(process(&mut context, &[mint_to_instruction(&program_id, &admin.pubkey(), &destination, 1)], &[])
+        .await
+        .is_err());
+
+    // Minting up to the cap works, one more token does not
+    process(
+        &mut context,
+        &[mint_to_instruction(&program_id, &rewards_multisig.pubkey(), &destination, 1_000)],
+        &[&rewards_multisig],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &destination).await, 1_000);
+    let err = process(
+        &mut context,
+        &[mint_to_instruction(&program_id, &rewards_multisig.pubkey(), &destination, 1)],
+        &[&rewards_multisig],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::SupplyCapExceeded as u32))
+    );
+
+    // Renouncing also clears the SPL mint authority
+    process(
+        &mut context,
+        &[set_mint_authority_instruction(&program_id, &rewards_multisig.pubkey(), None)],
+        &[&rewards_multisig],
+    )
+    .await
+    .unwrap();
+    let mint_account = context.banks_client.get_account(mint).await.unwrap().unwrap();
+    assert!(spl_token::state::Mint::unpack(&mint_account.data).unwrap().mint_authority.is_none());
+    assert!(process(
+        &mut context,
+        &[set_mint_authority_instruction(&program_id, &rewards_multisig.pubkey(), Some(admin.pubkey()))],
+        &[&rewards_multisig],
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn test_merkle_airdrop() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000).await;
+
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let collectors: Vec<Keypair> = (0..4).map(|_| Keypair::new()).collect();
+    let amounts = [100, 200, 300, 400];
+    let leaves: Vec<[u8; 32]> = collectors
+        .iter()
+        .zip(amounts)
+        .enumerate()
+        .map(|(i, (collector, amount))| airdrop_leaf(i as u64, &collector.pubkey(), amount))
+        .collect();
+    let left = hash_pair(leaves[0], leaves[1]);
+    let right = hash_pair(leaves[2], leaves[3]);
+    let root = hash_pair(left, right);
+
+    let admin = context.payer.pubkey();
+    process(&mut context, &[create_distributor_instruction(&program_id, &admin, 1, root, 1_000, 4)], &[])
+        .await
+        .unwrap();
+
+    let claimant = &collectors[2];
+    let destination = create_token_account(&mut context, &mint, &claimant.pubkey(), 0);
+    let proof = vec![leaves[3], left];
+
+    // A forged amount does not verify
+    let err = process(
+        &mut context,
+        &[claim_airdrop_instruction(&program_id, 1, &claimant.pubkey(), &destination, 2, 400, proof.clone())],
+        &[claimant],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::InvalidProof as u32))
+    );
+
+    process(
+        &mut context,
+        &[claim_airdrop_instruction(&program_id, 1, &claimant.pubkey(), &destination, 2, 300, proof.clone())],
+        &[claimant],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &destination).await, 300);
+
+    // The bitmap rejects a second claim of the same leaf
+    let err = process(
+        &mut context,
+        &[claim_airdrop_instruction(&program_id, 1, &claimant.pubkey(), &destination, 2, 300, proof)],
+        &[claimant],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VortexError::AlreadyClaimed as u32))
+    );
+}
+
+#[tokio::test]
+async fn test_staking_rewards_follow_halving_schedule() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    initialize(&mut context, &program_id, 1_000_000_000).await;
+    let admin = context.payer.insecure_clone();
+    let start = clock(&mut context).await.unix_timestamp;
+    set_clock(&mut context, start).await;
+    process(
+        &mut context,
+        &[
+            initialize_stake_pool_instruction(&program_id, &admin.pubkey()),
+            append_reward_epochs_instruction(&program_id, &admin.pubkey(), 3, 100, 40, 5_000),
+            fund_rewards_instruction(&program_id, &admin.pubkey(), 1_000_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let staker = Keypair::new();
+    let (mint, _) = Pubkey::find_program_address(&[MINT_SEED], &program_id);
+    let token_account = create_token_account(&mut context, &mint, &staker.pubkey(), 1_000);
+    fund_lamports(&mut context, &staker.pubkey()).await;
+    process(
+        &mut context,
+        &[stake_instruction(&program_id, &staker.pubkey(), &token_account, 1_000, 1_000)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    // 40, 20 and 10 per second over three epochs, then nothing
+    set_clock(&mut context, start + 1_000).await;
+    process(
+        &mut context,
+        &[claim_rewards_instruction(&program_id, &staker.pubkey(), &token_account)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut context, &token_account).await, 4_000 + 2_000 + 1_000);
+
+    // Appending after the schedule lapsed starts the new epochs now
+    process(
+        &mut context,
+        &[append_reward_epochs_instruction(&program_id, &admin.pubkey(), 1, 100, 5, 0)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let (schedule, _) = Pubkey::find_program_address(&[REWARD_SCHEDULE_SEED], &program_id);
+    let schedule = context.banks_client.get_account(schedule).await.unwrap().unwrap();
+    let schedule = RewardSchedule::try_from_slice(&schedule.data).unwrap();
+    assert_eq!(schedule.epochs.len(), 5);
+    assert_eq!(schedule.total_emitted(start + 1_000).unwrap(), 7_000);
+    assert_eq!(schedule.end_timestamp(), start + 1_100);
+}
@@ -0,0 +1,193 @@
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    system_program,
+    sysvar::Sysvar,
+};
+use crate::VortexError;
+
+// Create a rent-exempt account at a program-derived address
+pub fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    owner: &Pubkey,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+    if new_account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                new_account.key,
+                lamports,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+            &[signer_seeds],
+        );
+    }
+
+    // Someone already sent lamports to the address, which makes create_account fail, so top up the
+    // rent and allocate and assign it ourselves
+    let shortfall = lamports.saturating_sub(new_account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, new_account.key, shortfall),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(new_account.key, space as u64),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account.key, owner),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+// Create an SPL token account at a program-derived address
+pub fn create_token_account_pda<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    owner: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    create_pda_account(
+        payer,
+        new_account,
+        system_program,
+        &spl_token::id(),
+        spl_token::state::Account::LEN,
+        signer_seeds,
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account3(
+            token_program.key,
+            new_account.key,
+            mint.key,
+            owner,
+        )?,
+        &[new_account.clone(), mint.clone(), token_program.clone()],
+    )
+}
+
+// Move SPL tokens, signing with the given seeds when the authority is a PDA
+pub fn transfer_tokens<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            source.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[source.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        signer_seeds,
+    )
+}
+
+// Move lamports out of a system-owned signer
+pub fn transfer_lamports<'a>(
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &system_instruction::transfer(source.key, destination.key, amount),
+        &[source.clone(), destination.clone(), system_program.clone()],
+    )
+}
+
+// Grow or shrink a program-owned account, topping up rent from the payer
+pub fn resize_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_len: usize,
+) -> ProgramResult {
+    let required = Rent::get()?.minimum_balance(new_len);
+    let shortfall = required.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    account.realloc(new_len, false)
+}
+
+// Close a token account owned by a PDA, sending its rent to `destination`
+pub fn close_token_account<'a>(
+    token_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?,
+        &[account.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        signer_seeds,
+    )
+}
+
+// Close a program-owned account, sending its rent to `destination`
+pub fn close_program_account<'a>(account: &AccountInfo<'a>, destination: &AccountInfo<'a>) -> ProgramResult {
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+    Ok(())
+}
+
+// Read the upgrade authority from the program's ProgramData account
+pub fn upgrade_authority(program_id: &Pubkey, program_data: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+    let (expected, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data.key != expected || *program_data.owner != bpf_loader_upgradeable::id() {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    // Bincode layout: u32 variant (3 = ProgramData), u64 slot, then Option<Pubkey>
+    let data = program_data.data.borrow();
+    if data.len() < UpgradeableLoaderState::size_of_programdata_metadata() || data[..4] != 3u32.to_le_bytes() {
+        return Err(VortexError::InvalidAccount.into());
+    }
+    Ok(match data[12] {
+        0 => None,
+        _ => Some(Pubkey::try_from(&data[13..45]).map_err(|_| VortexError::InvalidAccount)?),
+    })
+}